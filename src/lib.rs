@@ -0,0 +1,18 @@
+//! TypeScript のサブセットの型検査器
+//!
+//! - [`arith`] は真偽値と数値、足し算、条件演算子だけの言語
+//! - [`basic`] は `arith` に変数、関数、`const` 宣言を加えた言語
+//!
+//! ```
+//! use tiny_ts::basic;
+//!
+//! let term = basic::parse("const x = 1; x ? 2 : 3;").unwrap();
+//! assert_eq!(
+//!     basic::typecheck(&term).unwrap_err().to_string(),
+//!     "boolean expected, but got number"
+//! );
+//! ```
+
+mod tiny_ts;
+
+pub use self::tiny_ts::{arith, basic, diagnostics, span};
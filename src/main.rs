@@ -1,7 +1,322 @@
-mod tiny_ts;
+use std::io::IsTerminal;
+use std::process::ExitCode;
 
-use tiny_ts::arith::{Term, parse};
+use tiny_ts::diagnostics::{Diagnostic, Style};
+use tiny_ts::{arith, basic};
 
-fn main() {
-    assert_eq!(parse("true"), Term::True);
+const USAGE: &str = "\
+Usage: tiny-ts [OPTIONS] <COMMAND> [FILE]
+
+Commands:
+  check  Parse and typecheck a program
+  parse  Parse a program and print its syntax tree
+  run    Typecheck a program and print its value
+
+Arguments:
+  [FILE]  Source file (reads standard input if omitted or `-`)
+
+Options:
+  --lang <LANG>   Language level: arith | basic [default: basic]
+  --color <WHEN>  Colorize diagnostics: auto | always | never [default: auto]
+  --infer         Infer parameter types instead of requiring annotations (basic only)
+  -h, --help      Print help
+";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Check,
+    Parse,
+    Run,
+}
+
+#[derive(Debug, PartialEq)]
+enum Lang {
+    Arith,
+    Basic,
+}
+
+#[derive(Debug, PartialEq)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    lang: Lang,
+    color: Color,
+    /// 型注釈を要求する型検査の代わりに型推論を使う
+    infer: bool,
+    /// `None` の場合は標準入力から読み込む
+    file: Option<String>,
+}
+
+/// `None` はヘルプの表示を要求されたことを表す
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut command = None;
+    let mut lang = Lang::Basic;
+    let mut color = Color::Auto;
+    let mut infer = false;
+    let mut file = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for `{}`", name))
+        };
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--lang" => {
+                lang = match value("--lang")?.as_str() {
+                    "arith" => Lang::Arith,
+                    "basic" => Lang::Basic,
+                    other => return Err(format!("unknown language `{}`", other)),
+                }
+            }
+            "--color" => {
+                color = match value("--color")?.as_str() {
+                    "auto" => Color::Auto,
+                    "always" => Color::Always,
+                    "never" => Color::Never,
+                    other => return Err(format!("unknown color mode `{}`", other)),
+                }
+            }
+            "--infer" => infer = true,
+            "-" => match (&command, &file) {
+                (Some(_), None) => file = Some(arg),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => match (&command, &file) {
+                (None, _) => {
+                    command = Some(match arg.as_str() {
+                        "check" => Command::Check,
+                        "parse" => Command::Parse,
+                        "run" => Command::Run,
+                        other => return Err(format!("unknown command `{}`", other)),
+                    })
+                }
+                (Some(_), None) => file = Some(arg),
+                (Some(_), Some(_)) => return Err(format!("unexpected argument `{}`", arg)),
+            },
+        }
+    }
+
+    let command = command.ok_or_else(|| "missing command".to_owned())?;
+    if infer && lang != Lang::Basic {
+        return Err("`--infer` requires `--lang basic`".to_owned());
+    }
+    Ok(Some(Args {
+        command,
+        lang,
+        color,
+        infer,
+        file: file.filter(|file| file != "-"),
+    }))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let (file_name, source) = match &args.file {
+        Some(file) => match std::fs::read_to_string(file) {
+            Ok(source) => (file.as_str(), source),
+            Err(e) => {
+                eprintln!("error: cannot read `{}`: {}", file, e);
+                return ExitCode::from(2);
+            }
+        },
+        None => match std::io::read_to_string(std::io::stdin()) {
+            Ok(source) => ("<stdin>", source),
+            Err(e) => {
+                eprintln!("error: cannot read standard input: {}", e);
+                return ExitCode::from(2);
+            }
+        },
+    };
+
+    let style = match args.color {
+        Color::Always => Style::Ansi,
+        Color::Never => Style::Plain,
+        Color::Auto => {
+            if std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+                Style::Ansi
+            } else {
+                Style::Plain
+            }
+        }
+    };
+
+    let diagnostics = match (args.lang, args.command) {
+        (Lang::Arith, command @ (Command::Check | Command::Run)) => match arith::parse(&source) {
+            Ok(term) => match arith::typecheck(&term) {
+                Ok(typ) if command == Command::Check => {
+                    println!("{}", typ);
+                    vec![]
+                }
+                Ok(_) => match arith::eval(&term) {
+                    Ok(value) => {
+                        println!("{}", value);
+                        vec![]
+                    }
+                    Err(e) => vec![Diagnostic::from(&e)],
+                },
+                Err(e) => vec![Diagnostic::from(&e)],
+            },
+            Err(e) => vec![Diagnostic::from(&e)],
+        },
+        (Lang::Arith, Command::Parse) => match arith::parse(&source) {
+            Ok(term) => {
+                println!("{:#?}", term);
+                vec![]
+            }
+            Err(e) => vec![Diagnostic::from(&e)],
+        },
+        (Lang::Basic, command @ (Command::Check | Command::Run)) => {
+            let (term, parse_errors) = basic::parse_with_recovery(&source);
+            let (typ, type_errors) = if args.infer {
+                basic::infer_all(&term)
+            } else {
+                basic::typecheck_all(&term)
+            };
+            if !parse_errors.is_empty() || !type_errors.is_empty() {
+                parse_errors
+                    .iter()
+                    .map(Diagnostic::from)
+                    .chain(type_errors.iter().map(Diagnostic::from))
+                    .collect()
+            } else if command == Command::Check {
+                println!("{}", typ);
+                vec![]
+            } else {
+                match basic::eval(&term) {
+                    Ok(value) => {
+                        println!("{}", value);
+                        vec![]
+                    }
+                    Err(e) => vec![Diagnostic::from(&e)],
+                }
+            }
+        }
+        (Lang::Basic, Command::Parse) => {
+            let (term, errors) = basic::parse_with_recovery(&source);
+            println!("{:#?}", term);
+            errors.iter().map(Diagnostic::from).collect()
+        }
+    };
+
+    for diagnostic in &diagnostics {
+        eprint!("{}", diagnostic.render(file_name, &source, style));
+    }
+    if diagnostics.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!(
+            "{} error{} found",
+            diagnostics.len(),
+            if diagnostics.len() == 1 { "" } else { "s" }
+        );
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            args(&["check", "main.ts"]),
+            Ok(Some(Args {
+                command: Command::Check,
+                lang: Lang::Basic,
+                color: Color::Auto,
+                infer: false,
+                file: Some("main.ts".to_owned()),
+            }))
+        );
+        assert_eq!(
+            args(&["--lang", "arith", "parse"]),
+            Ok(Some(Args {
+                command: Command::Parse,
+                lang: Lang::Arith,
+                color: Color::Auto,
+                infer: false,
+                file: None,
+            }))
+        );
+        assert_eq!(
+            args(&["check", "--color=never", "-", "--lang=basic"]),
+            Ok(Some(Args {
+                command: Command::Check,
+                lang: Lang::Basic,
+                color: Color::Never,
+                infer: false,
+                file: None,
+            }))
+        );
+        assert_eq!(
+            args(&["run", "--infer", "main.ts"]),
+            Ok(Some(Args {
+                command: Command::Run,
+                lang: Lang::Basic,
+                color: Color::Auto,
+                infer: true,
+                file: Some("main.ts".to_owned()),
+            }))
+        );
+        assert_eq!(args(&["check", "--help"]), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_args_error() {
+        assert_eq!(args(&[]), Err("missing command".to_owned()));
+        assert_eq!(args(&["build"]), Err("unknown command `build`".to_owned()));
+        assert_eq!(
+            args(&["--lang", "full", "check"]),
+            Err("unknown language `full`".to_owned())
+        );
+        assert_eq!(
+            args(&["check", "--lang"]),
+            Err("missing value for `--lang`".to_owned())
+        );
+        assert_eq!(
+            args(&["check", "a.ts", "b.ts"]),
+            Err("unexpected argument `b.ts`".to_owned())
+        );
+        assert_eq!(
+            args(&["-v", "check"]),
+            Err("unknown option `-v`".to_owned())
+        );
+        assert_eq!(
+            args(&["--lang=arith", "--infer", "check"]),
+            Err("`--infer` requires `--lang basic`".to_owned())
+        );
+    }
 }
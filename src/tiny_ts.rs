@@ -1,2 +1,5 @@
 pub mod arith;
 pub mod basic;
+pub mod diagnostics;
+mod number;
+pub mod span;
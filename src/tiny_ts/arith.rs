@@ -1,69 +1,110 @@
+mod eval;
 mod parse;
 mod term;
 mod token;
 mod typecheck;
 
-pub use self::parse::parse;
+pub use self::eval::{EvalError, Value, eval};
+pub use self::parse::{ParseError, parse};
 pub use self::term::Term;
-pub use self::typecheck::{Type, typecheck};
+pub use self::token::Token;
+pub use self::typecheck::{Type, TypeError, typecheck};
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::tiny_ts::span::Span;
+
     // number 型同士以外の足し算をしないこと
 
     #[test]
     fn test_1_plus_2_is_ok() {
-        typecheck(parse("1 + 2"));
+        assert_eq!(typecheck(&parse("1 + 2").unwrap()), Ok(Type::Number));
     }
 
-    #[should_panic]
     #[test]
     fn test_1_plus_true_is_ng() {
-        typecheck(parse("1 + true"));
+        assert_eq!(
+            typecheck(&parse("1 + true").unwrap()),
+            Err(TypeError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Boolean,
+                span: Span::new(4, 8),
+            })
+        );
     }
 
-    #[should_panic]
     #[test]
     fn test_false_plus_true_is_ng() {
-        typecheck(parse("1 + true"));
+        assert_eq!(
+            typecheck(&parse("false + true").unwrap()),
+            Err(TypeError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Boolean,
+                span: Span::new(0, 5),
+            })
+        );
     }
 
     // 条件演算子の条件式が boolean 型であること
 
     #[test]
     fn test_true_quest_is_ok() {
-        typecheck(parse("true ? 0 : 0"));
+        assert_eq!(typecheck(&parse("true ? 0 : 0").unwrap()), Ok(Type::Number));
     }
 
-    #[should_panic]
     #[test]
     fn test_1_quest_is_ng() {
-        typecheck(parse("1 ? 0 : 0"));
+        assert_eq!(
+            typecheck(&parse("1 ? 0 : 0").unwrap()),
+            Err(TypeError::NonBooleanCondition {
+                actual: Type::Number,
+                span: Span::new(0, 1),
+            })
+        );
     }
 
     // 条件演算子の返す型が一致すること
 
     #[test]
     fn test_true_quest_true_colon_false_is_ok() {
-        typecheck(parse("true ? true : false"));
+        assert_eq!(
+            typecheck(&parse("true ? true : false").unwrap()),
+            Ok(Type::Boolean)
+        );
     }
 
     #[test]
     fn test_true_quest_1_colon_2_is_ok() {
-        typecheck(parse("true ? 1 : 2"));
+        assert_eq!(typecheck(&parse("true ? 1 : 2").unwrap()), Ok(Type::Number));
     }
 
-    #[should_panic]
     #[test]
     fn test_true_quest_true_colon_1_is_ok() {
-        typecheck(parse("true ? true : 1"));
+        assert_eq!(
+            typecheck(&parse("true ? true : 1").unwrap()),
+            Err(TypeError::BranchMismatch {
+                thn: Type::Boolean,
+                els: Type::Number,
+                span: Span::new(0, 15),
+                thn_span: Span::new(7, 11),
+                els_span: Span::new(14, 15),
+            })
+        );
     }
 
-    #[should_panic]
     #[test]
     fn test_true_quest_2_colon_false_is_ok() {
-        typecheck(parse("true ? 2 : false"));
+        assert_eq!(
+            typecheck(&parse("true ? 2 : false").unwrap()),
+            Err(TypeError::BranchMismatch {
+                thn: Type::Number,
+                els: Type::Boolean,
+                span: Span::new(0, 16),
+                thn_span: Span::new(7, 8),
+                els_span: Span::new(11, 16),
+            })
+        );
     }
 }
@@ -0,0 +1,117 @@
+use crate::tiny_ts::arith::Term;
+use crate::tiny_ts::span::Span;
+
+/// 評価結果の値
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    Number(f64),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// 実行時エラー
+///
+/// 型検査を通ったプログラムでは起こらない
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    /// 期待した種類と異なる値 (例: `1 + true`)
+    UnexpectedValue {
+        expected: &'static str,
+        actual: String,
+        span: Span,
+    },
+}
+
+impl EvalError {
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::UnexpectedValue { span, .. } => *span,
+        }
+    }
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::UnexpectedValue {
+                expected, actual, ..
+            } => write!(f, "{} expected, but got `{}`", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+pub fn eval(t: &Term) -> Result<Value, EvalError> {
+    match t {
+        Term::False(_) => Ok(Value::Boolean(false)),
+        Term::True(_) => Ok(Value::Boolean(true)),
+        Term::Number(n, _) => Ok(Value::Number(*n)),
+        Term::Add(left, right, _) => Ok(Value::Number(eval_number(left)? + eval_number(right)?)),
+        Term::If(cond, thn, els, _) => match eval(cond)? {
+            Value::Boolean(true) => eval(thn),
+            Value::Boolean(false) => eval(els),
+            actual => Err(EvalError::UnexpectedValue {
+                expected: "boolean",
+                actual: actual.to_string(),
+                span: cond.span(),
+            }),
+        },
+    }
+}
+
+fn eval_number(t: &Term) -> Result<f64, EvalError> {
+    match eval(t)? {
+        Value::Number(n) => Ok(n),
+        actual => Err(EvalError::UnexpectedValue {
+            expected: "number",
+            actual: actual.to_string(),
+            span: t.span(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tiny_ts::arith::parse;
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(eval(&parse("true").unwrap()), Ok(Value::Boolean(true)));
+        assert_eq!(eval(&parse("1 + 2").unwrap()), Ok(Value::Number(3.0)));
+        assert_eq!(eval(&parse("255 + 1").unwrap()), Ok(Value::Number(256.0)));
+        assert_eq!(
+            eval(&parse("0xff + 0b1").unwrap()),
+            Ok(Value::Number(256.0))
+        );
+        assert_eq!(
+            eval(&parse("1_000_000 + 0.5").unwrap()),
+            Ok(Value::Number(1_000_000.5))
+        );
+        assert_eq!(
+            eval(&parse("false ? 1 : true ? 2 + 3 : 4").unwrap()),
+            Ok(Value::Number(5.0))
+        );
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let err = eval(&parse("1 + true").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got `true`");
+        assert_eq!(err.span().range(), 4..8);
+
+        let err = eval(&parse("1 ? 2 : 3").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "boolean expected, but got `1`");
+        assert_eq!(err.span().range(), 0..1);
+    }
+}
@@ -1,62 +1,203 @@
 use crate::tiny_ts::arith::term::Term;
 use crate::tiny_ts::arith::token::Token;
+#[cfg(test)]
+use crate::tiny_ts::span::ClearSpans;
+use crate::tiny_ts::span::Span;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// 期待していないトークンを読み込んだ (例: `1 + ?`)
+    UnexpectedToken {
+        found: Token,
+        expected: Vec<&'static str>,
+        span: Span,
+    },
+    /// 入力が途中で終わった (例: `1 +`)
+    UnexpectedEof {
+        expected: Vec<&'static str>,
+        span: Span,
+    },
+    /// 字句解析できない文字列を読み込んだ (例: `@`)
+    InvalidToken { text: String, span: Span },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::InvalidToken { span, .. } => *span,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for ParseError {
+    fn clear_spans(&mut self) {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::InvalidToken { span, .. } => span.clear_spans(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                found, expected, ..
+            } => write!(f, "{} expected, but got `{}`", expected.join(" or "), found),
+            ParseError::UnexpectedEof { expected, .. } => {
+                write!(
+                    f,
+                    "{} expected, but got end of input",
+                    expected.join(" or ")
+                )
+            }
+            ParseError::InvalidToken { text, .. } => write!(f, "invalid token `{}`", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
-pub fn parse(s: &str) -> Term {
-    let lexer = <Token as logos::Logos>::lexer(s);
-    read_ternary(&mut lexer.peekable())
+pub fn parse(s: &str) -> Result<Term, ParseError> {
+    let mut tokens = Tokens::new(s);
+    let term = read_ternary(&mut tokens)?;
+    match tokens.peek()? {
+        None => Ok(term),
+        Some(_) => Err(tokens.unexpected(&["`+`", "`?`", "end of input"])),
+    }
 }
 
-// unary = false | true | integer
+/// 先読み可能なトークン列
+struct Tokens<'a> {
+    source: &'a str,
+    iter: std::iter::Peekable<logos::SpannedIter<'a, Token>>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str) -> Self {
+        let lexer = <Token as logos::Logos>::lexer(source);
+        Self {
+            source,
+            iter: lexer.spanned().peekable(),
+        }
+    }
+
+    /// 次のトークンを読み進めずに返す (入力の終わりでは `None`)
+    fn peek(&mut self) -> Result<Option<&Token>, ParseError> {
+        match self.iter.peek() {
+            None => Ok(None),
+            Some((Ok(token), _)) => Ok(Some(token)),
+            Some((Err(()), span)) => Err(ParseError::InvalidToken {
+                text: self.source[span.clone()].to_owned(),
+                span: Span::from(span.clone()),
+            }),
+        }
+    }
+
+    /// 次のトークンを読み進める (入力の終わりでは `expected` を期待していたとしてエラー)
+    fn next(&mut self, expected: &[&'static str]) -> Result<(Token, Span), ParseError> {
+        match self.iter.next() {
+            None => Err(ParseError::UnexpectedEof {
+                expected: expected.to_vec(),
+                span: Span::new(self.source.len(), self.source.len()),
+            }),
+            Some((Ok(token), span)) => Ok((token, Span::from(span))),
+            Some((Err(()), span)) => Err(ParseError::InvalidToken {
+                text: self.source[span.clone()].to_owned(),
+                span: Span::from(span),
+            }),
+        }
+    }
+
+    /// 次のトークンが `token` であることを確かめて読み進める
+    fn expect(&mut self, token: Token, name: &'static str) -> Result<Span, ParseError> {
+        let (found, span) = self.next(&[name])?;
+        if found == token {
+            Ok(span)
+        } else {
+            Err(ParseError::UnexpectedToken {
+                found,
+                expected: vec![name],
+                span,
+            })
+        }
+    }
+
+    /// 次のトークンが `expected` のいずれでもなかったことを表すエラー
+    fn unexpected(&mut self, expected: &[&'static str]) -> ParseError {
+        match self.next(expected) {
+            Ok((found, span)) => ParseError::UnexpectedToken {
+                found,
+                expected: expected.to_vec(),
+                span,
+            },
+            Err(e) => e,
+        }
+    }
+}
+
+// unary = false | true | number | paren_l ternary paren_r
 // binary = unary | unary plus binary
 // ternary = binary | binary question ternary colon ternary
 
-fn read_binary(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let token = iter.next().unwrap().unwrap();
-    let unary = match token {
-        Token::False => Term::False,
-        Token::True => Term::True,
-        Token::Integer(i) => Term::Integer(i),
-        Token::Plus | Token::Quest | Token::Colon => unreachable!(),
-    };
-    match iter.peek() {
-        None => unary,
-        Some(token) => {
-            let token = token.as_ref().unwrap();
-            match token {
-                Token::False | Token::True | Token::Integer(_) => unreachable!(),
-                Token::Quest | Token::Colon => unary,
-                Token::Plus => {
-                    assert!(matches!(iter.next(), Some(Ok(Token::Plus))));
-                    let left = unary;
-                    let right = read_binary(iter);
-                    Term::Add(Box::new(left), Box::new(right))
+fn read_unary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    const EXPECTED: &[&str] = &["`false`", "`true`", "number", "`(`"];
+    let (token, span) = tokens.next(EXPECTED)?;
+    match token {
+        Token::False => Ok(Term::False(span)),
+        Token::True => Ok(Term::True(span)),
+        Token::Number(i) => Ok(Term::Number(i, span)),
+        Token::ParenL => {
+            let term = read_ternary(tokens)?;
+            match tokens.peek()? {
+                Some(Token::ParenR) => {
+                    tokens.expect(Token::ParenR, "`)`")?;
+                    Ok(term)
                 }
+                _ => Err(tokens.unexpected(&["`+`", "`?`", "`)`"])),
             }
         }
+        Token::Plus | Token::Quest | Token::Colon | Token::ParenR => {
+            Err(ParseError::UnexpectedToken {
+                found: token,
+                expected: EXPECTED.to_vec(),
+                span,
+            })
+        }
     }
 }
 
-fn read_ternary(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let binary = read_binary(iter);
-    match iter.peek() {
-        None => binary,
-        Some(token) => {
-            let token = token.as_ref().unwrap();
-            match token {
-                Token::False | Token::True | Token::Integer(_) | Token::Plus => {
-                    unreachable!()
-                }
-                Token::Quest => {
-                    assert!(matches!(iter.next(), Some(Ok(Token::Quest))));
-                    let cond = binary;
-                    let thn = read_ternary(iter);
-                    assert!(matches!(iter.next(), Some(Ok(Token::Colon))));
-                    let els = read_ternary(iter);
-                    Term::If(Box::new(cond), Box::new(thn), Box::new(els))
-                }
-                Token::Colon => binary,
-            }
+fn read_binary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let unary = read_unary(tokens)?;
+    match tokens.peek()? {
+        Some(Token::Plus) => {
+            tokens.expect(Token::Plus, "`+`")?;
+            let left = unary;
+            let right = read_binary(tokens)?;
+            let span = left.span().to(right.span());
+            Ok(Term::Add(Box::new(left), Box::new(right), span))
+        }
+        _ => Ok(unary),
+    }
+}
+
+fn read_ternary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let binary = read_binary(tokens)?;
+    match tokens.peek()? {
+        Some(Token::Quest) => {
+            tokens.expect(Token::Quest, "`?`")?;
+            let cond = binary;
+            let thn = read_ternary(tokens)?;
+            tokens.expect(Token::Colon, "`:`")?;
+            let els = read_ternary(tokens)?;
+            let span = cond.span().to(els.span());
+            Ok(Term::If(Box::new(cond), Box::new(thn), Box::new(els), span))
         }
+        _ => Ok(binary),
     }
 }
 
@@ -64,61 +205,200 @@ fn read_ternary(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term
 mod tests {
     use super::*;
 
+    use crate::tiny_ts::span::without_spans;
+
+    const S: Span = Span::new(0, 0);
+
     #[test]
     fn test() {
         fn test_parse(s: &str, expected: Term) {
-            let term = parse(s);
-            assert_eq!(term, expected);
+            let term = parse(s).unwrap();
+            assert_eq!(without_spans(term), expected);
         }
 
-        test_parse("true", Term::True);
-        test_parse("false", Term::False);
-        test_parse("0", Term::Integer(0));
+        test_parse("true", Term::True(S));
+        test_parse("false", Term::False(S));
+        test_parse("0", Term::Number(0.0, S));
         test_parse(
             "1 + 2",
-            Term::Add(Box::new(Term::Integer(1)), Box::new(Term::Integer(2))),
+            Term::Add(
+                Box::new(Term::Number(1.0, S)),
+                Box::new(Term::Number(2.0, S)),
+                S,
+            ),
         );
         test_parse(
             "3 + 4 + 5",
             Term::Add(
-                Box::new(Term::Integer(3)),
+                Box::new(Term::Number(3.0, S)),
                 Box::new(Term::Add(
-                    Box::new(Term::Integer(4)),
-                    Box::new(Term::Integer(5)),
+                    Box::new(Term::Number(4.0, S)),
+                    Box::new(Term::Number(5.0, S)),
+                    S,
                 )),
+                S,
             ),
         );
         test_parse(
             "true ? 6 : 7",
             Term::If(
-                Box::new(Term::True),
-                Box::new(Term::Integer(6)),
-                Box::new(Term::Integer(7)),
+                Box::new(Term::True(S)),
+                Box::new(Term::Number(6.0, S)),
+                Box::new(Term::Number(7.0, S)),
+                S,
             ),
         );
         test_parse(
             "true ? true ? 8 : 9 : 10",
             Term::If(
-                Box::new(Term::True),
+                Box::new(Term::True(S)),
                 Box::new(Term::If(
-                    Box::new(Term::True),
-                    Box::new(Term::Integer(8)),
-                    Box::new(Term::Integer(9)),
+                    Box::new(Term::True(S)),
+                    Box::new(Term::Number(8.0, S)),
+                    Box::new(Term::Number(9.0, S)),
+                    S,
                 )),
-                Box::new(Term::Integer(10)),
+                Box::new(Term::Number(10.0, S)),
+                S,
             ),
         );
         test_parse(
             "true ? 11 : true ? 12 : 13",
             Term::If(
-                Box::new(Term::True),
-                Box::new(Term::Integer(11)),
+                Box::new(Term::True(S)),
+                Box::new(Term::Number(11.0, S)),
                 Box::new(Term::If(
-                    Box::new(Term::True),
-                    Box::new(Term::Integer(12)),
-                    Box::new(Term::Integer(13)),
+                    Box::new(Term::True(S)),
+                    Box::new(Term::Number(12.0, S)),
+                    Box::new(Term::Number(13.0, S)),
+                    S,
                 )),
+                S,
             ),
         );
     }
+
+    #[test]
+    fn test_paren() {
+        assert_eq!(
+            without_spans(parse("(1 + 2) + 3")),
+            Ok(Term::Add(
+                Box::new(Term::Add(
+                    Box::new(Term::Number(1.0, S)),
+                    Box::new(Term::Number(2.0, S)),
+                    S,
+                )),
+                Box::new(Term::Number(3.0, S)),
+                S,
+            ))
+        );
+        assert_eq!(
+            without_spans(parse("(true ? false : true) ? (1) : ((2))")),
+            Ok(Term::If(
+                Box::new(Term::If(
+                    Box::new(Term::True(S)),
+                    Box::new(Term::False(S)),
+                    Box::new(Term::True(S)),
+                    S,
+                )),
+                Box::new(Term::Number(1.0, S)),
+                Box::new(Term::Number(2.0, S)),
+                S,
+            ))
+        );
+        assert_eq!(
+            without_spans(parse("(1 + 2")),
+            Err(ParseError::UnexpectedEof {
+                expected: vec!["`+`", "`?`", "`)`"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("1 + 2)")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: vec!["`+`", "`?`", "end of input"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("()")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: vec!["`false`", "`true`", "number", "`(`"],
+                span: S,
+            })
+        );
+    }
+
+    #[test]
+    fn test_span() {
+        let term = parse("true ? 1 + 2 : 3").unwrap();
+        assert_eq!(term.span().range(), 0..16);
+        let Term::If(cond, thn, els, _) = term else {
+            panic!()
+        };
+        assert_eq!(cond.span().range(), 0..4);
+        assert_eq!(thn.span().range(), 7..12);
+        assert_eq!(els.span().range(), 15..16);
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(
+            without_spans(parse("1 + ?")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Quest,
+                expected: vec!["`false`", "`true`", "number", "`(`"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("true ? 1")),
+            Err(ParseError::UnexpectedEof {
+                expected: vec!["`:`"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("1 2")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Number(2.0),
+                expected: vec!["`+`", "`?`", "end of input"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("1 + @")),
+            Err(ParseError::InvalidToken {
+                text: "@".to_owned(),
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("")),
+            Err(ParseError::UnexpectedEof {
+                expected: vec!["`false`", "`true`", "number", "`(`"],
+                span: S,
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_message() {
+        let err = parse("true ? 1 2").unwrap_err();
+        assert_eq!(err.span().range(), 9..10);
+        assert_eq!(err.to_string(), "`:` expected, but got `2`");
+
+        let err = parse("1 +").unwrap_err();
+        assert_eq!(err.span().range(), 3..3);
+        assert_eq!(
+            err.to_string(),
+            "`false` or `true` or number or `(` expected, but got end of input"
+        );
+
+        let err = parse("1 + @").unwrap_err();
+        assert_eq!(err.span().range(), 4..5);
+        assert_eq!(err.to_string(), "invalid token `@`");
+    }
 }
@@ -1,8 +1,44 @@
+#[cfg(test)]
+use crate::tiny_ts::span::ClearSpans;
+use crate::tiny_ts::span::Span;
+
 #[derive(Debug, PartialEq)]
 pub enum Term {
-    False,
-    True,
-    Integer(u8),
-    Add(Box<Term>, Box<Term>),
-    If(Box<Term>, Box<Term>, Box<Term>),
+    False(Span),
+    True(Span),
+    Number(f64, Span),
+    Add(Box<Term>, Box<Term>, Span),
+    If(Box<Term>, Box<Term>, Box<Term>, Span),
+}
+
+impl Term {
+    pub fn span(&self) -> Span {
+        match self {
+            Term::False(span)
+            | Term::True(span)
+            | Term::Number(_, span)
+            | Term::Add(_, _, span)
+            | Term::If(_, _, _, span) => *span,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for Term {
+    fn clear_spans(&mut self) {
+        match self {
+            Term::False(span) | Term::True(span) | Term::Number(_, span) => span.clear_spans(),
+            Term::Add(left, right, span) => {
+                left.clear_spans();
+                right.clear_spans();
+                span.clear_spans();
+            }
+            Term::If(cond, thn, els, span) => {
+                cond.clear_spans();
+                thn.clear_spans();
+                els.clear_spans();
+                span.clear_spans();
+            }
+        }
+    }
 }
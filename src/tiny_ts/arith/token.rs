@@ -1,18 +1,41 @@
-#[derive(Debug, PartialEq, logos::Logos)]
+use crate::tiny_ts::number::parse_number;
+
+#[derive(Clone, Debug, PartialEq, logos::Logos)]
 #[logos(skip r"\s+")]
 pub enum Token {
     #[token("false")]
     False,
     #[token("true")]
     True,
-    #[regex("0|[1-9][0-9]{0,}", |lexer| lexer.slice().parse::<u8>().ok())]
-    Integer(u8),
+    #[regex(r"(0|[1-9](_?[0-9])*)(\.[0-9](_?[0-9])*)?", |lexer| parse_number(lexer.slice(), 10))]
+    #[regex(r"0[xX][0-9a-fA-F](_?[0-9a-fA-F])*", |lexer| parse_number(&lexer.slice()[2..], 16))]
+    #[regex(r"0[bB][01](_?[01])*", |lexer| parse_number(&lexer.slice()[2..], 2))]
+    Number(f64),
     #[token("+")]
     Plus,
     #[token("?")]
     Quest,
     #[token(":")]
     Colon,
+    #[token("(")]
+    ParenL,
+    #[token(")")]
+    ParenR,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::False => write!(f, "false"),
+            Token::True => write!(f, "true"),
+            Token::Number(i) => write!(f, "{}", i),
+            Token::Plus => write!(f, "+"),
+            Token::Quest => write!(f, "?"),
+            Token::Colon => write!(f, ":"),
+            Token::ParenL => write!(f, "("),
+            Token::ParenR => write!(f, ")"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -31,7 +54,7 @@ mod tests {
         assert_eq!(lexer.span(), 5..6);
         assert_eq!(lexer.slice(), "?");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(0))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(0.0))));
         assert_eq!(lexer.span(), 7..8);
         assert_eq!(lexer.slice(), "0");
 
@@ -39,7 +62,7 @@ mod tests {
         assert_eq!(lexer.span(), 9..10);
         assert_eq!(lexer.slice(), ":");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(1))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(1.0))));
         assert_eq!(lexer.span(), 11..12);
         assert_eq!(lexer.slice(), "1");
 
@@ -47,10 +70,27 @@ mod tests {
         assert_eq!(lexer.span(), 13..14);
         assert_eq!(lexer.slice(), "+");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(2))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(2.0))));
         assert_eq!(lexer.span(), 15..16);
         assert_eq!(lexer.slice(), "2");
 
         assert_eq!(lexer.next(), None);
     }
+
+    #[test]
+    fn test_number() {
+        let lexer = <Token as logos::Logos>::lexer("4294967296 + 0.25 + 0x1_0 + 0b11");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::Number(4294967296.0)), 0..10),
+                (Ok(Token::Plus), 11..12),
+                (Ok(Token::Number(0.25)), 13..17),
+                (Ok(Token::Plus), 18..19),
+                (Ok(Token::Number(16.0)), 20..25),
+                (Ok(Token::Plus), 26..27),
+                (Ok(Token::Number(3.0)), 28..32),
+            ]
+        );
+    }
 }
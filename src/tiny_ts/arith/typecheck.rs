@@ -1,51 +1,180 @@
 use crate::tiny_ts::arith::Term;
+use crate::tiny_ts::span::Span;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Type {
     Boolean,
-    Integer,
+    Number,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "boolean"),
+            Type::Number => write!(f, "number"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TypeError {
+    /// 期待した型と異なる (例: `1 + true`)
+    TypeMismatch {
+        expected: Type,
+        actual: Type,
+        span: Span,
+    },
+    /// 条件式が boolean 型でない (例: `1 ? 2 : 3`)
+    NonBooleanCondition { actual: Type, span: Span },
+    /// 条件演算子の then と else の型が異なる (例: `true ? 1 : false`)
+    BranchMismatch {
+        thn: Type,
+        els: Type,
+        span: Span,
+        thn_span: Span,
+        els_span: Span,
+    },
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::TypeMismatch { span, .. }
+            | TypeError::NonBooleanCondition { span, .. }
+            | TypeError::BranchMismatch { span, .. } => *span,
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::TypeMismatch {
+                expected, actual, ..
+            } => {
+                write!(f, "{} expected, but got {}", expected, actual)
+            }
+            TypeError::NonBooleanCondition { actual, .. } => {
+                write!(f, "boolean expected, but got {}", actual)
+            }
+            TypeError::BranchMismatch { thn, els, .. } => {
+                write!(f, "then and else have different types: {} and {}", thn, els)
+            }
+        }
+    }
 }
 
-pub fn typecheck(t: Term) -> Type {
+impl std::error::Error for TypeError {}
+
+pub fn typecheck(t: &Term) -> Result<Type, TypeError> {
     match t {
-        Term::False | Term::True => Type::Boolean,
-        Term::Integer(_) => Type::Integer,
-        Term::Add(left, right) => {
-            assert_eq!(typecheck(*left), Type::Integer, "integer expected");
-            assert_eq!(typecheck(*right), Type::Integer, "integer expected");
-            Type::Integer
+        Term::False(_) | Term::True(_) => Ok(Type::Boolean),
+        Term::Number(_, _) => Ok(Type::Number),
+        Term::Add(left, right, _) => {
+            expect_type(left, Type::Number)?;
+            expect_type(right, Type::Number)?;
+            Ok(Type::Number)
         }
-        Term::If(cond, thn, els) => {
-            assert_eq!(typecheck(*cond), Type::Boolean, "boolean expected");
-            let thn_type = typecheck(*thn);
-            assert_eq!(
-                thn_type,
-                typecheck(*els),
-                "then and else have different types"
-            );
-            thn_type
+        Term::If(cond, thn, els, span) => {
+            let cond_type = typecheck(cond)?;
+            if cond_type != Type::Boolean {
+                return Err(TypeError::NonBooleanCondition {
+                    actual: cond_type,
+                    span: cond.span(),
+                });
+            }
+            let thn_type = typecheck(thn)?;
+            let els_type = typecheck(els)?;
+            if thn_type != els_type {
+                return Err(TypeError::BranchMismatch {
+                    thn: thn_type,
+                    els: els_type,
+                    span: *span,
+                    thn_span: thn.span(),
+                    els_span: els.span(),
+                });
+            }
+            Ok(thn_type)
         }
     }
 }
 
+fn expect_type(t: &Term, expected: Type) -> Result<(), TypeError> {
+    let actual = typecheck(t)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(TypeError::TypeMismatch {
+            expected,
+            actual,
+            span: t.span(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use crate::tiny_ts::arith::parse;
+    use crate::tiny_ts::span::LineIndex;
 
     #[test]
     fn test() {
-        let term = parse("false");
-        assert_eq!(typecheck(term), Type::Boolean);
+        let term = parse("false").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
+
+        let term = parse("true").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
 
-        let term = parse("true");
-        assert_eq!(typecheck(term), Type::Boolean);
+        let term = parse("0").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("true ? 0 : 1 + 2").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let term = parse("1 + true").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number expected, but got boolean"
+        );
+
+        let term = parse("1 ? 2 : 3").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "boolean expected, but got number"
+        );
+
+        let term = parse("true ? 1 : false").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "then and else have different types: number and boolean"
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let source = "true ? 1 + false : 2";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.span().range(), 11..16);
 
-        let term = parse("0");
-        assert_eq!(typecheck(term), Type::Integer);
+        let source = "true +\n  1 ? 2 : 3";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        let line_col = LineIndex::new(source).line_col(err.span().start);
+        assert_eq!(
+            format!("{}: {}", line_col, err),
+            "1:1: number expected, but got boolean"
+        );
 
-        let term = parse("true ? 0 : 1 + 2");
-        assert_eq!(typecheck(term), Type::Integer);
+        let source = "1 + 2 ? 3 : 4";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        let line_col = LineIndex::new(source).line_col(err.span().start);
+        assert_eq!(
+            format!("{}: {}", line_col, err),
+            "1:1: boolean expected, but got number"
+        );
     }
 }
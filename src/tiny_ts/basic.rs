@@ -1,10 +1,16 @@
+mod eval;
+mod infer;
 mod parse;
-mod term;
+mod resolve;
+pub mod term;
 mod token;
-mod typ;
-// mod typecheck;
+pub mod typ;
+mod typecheck;
 
-pub use self::parse::parse;
-// pub use self::term::Term;
+pub use self::eval::{EvalError, Value, eval};
+pub use self::infer::{infer, infer_all};
+pub use self::parse::{ParseError, parse, parse_with_recovery};
+pub use self::term::Term;
+pub use self::token::Token;
 pub use self::typ::Type;
-// pub use self::typecheck::typecheck;
+pub use self::typecheck::{TypeError, typecheck, typecheck_all};
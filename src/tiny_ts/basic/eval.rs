@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::tiny_ts::basic::Term;
+use crate::tiny_ts::basic::term::{FuncDecl, Param};
+use crate::tiny_ts::span::Span;
+
+/// 値環境 (変数名から値への対応)
+type Env<'a> = HashMap<String, Value<'a>>;
+
+/// 評価結果の値
+///
+/// オブジェクトと関数は `Rc` のポインタで同一性を区別する (`===` で比べる)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Undefined,
+    /// オブジェクト (プロパティは書いた順に並ぶ)
+    Object(Rc<Vec<(String, Value<'a>)>>),
+    /// 関数と、それが定義された時点の環境
+    Closure {
+        params: &'a [Param],
+        body: &'a Term,
+        env: Rc<Env<'a>>,
+    },
+    /// `function` で宣言した関数と、宣言された時点の環境
+    ///
+    /// 呼び出すときに同じ並びで宣言された関数を環境に加えることで、再帰呼び出しを可能にする
+    RecClosure {
+        funcs: &'a [FuncDecl],
+        index: usize,
+        env: Rc<Env<'a>>,
+    },
+}
+
+impl Value<'_> {
+    /// `typeof` 演算子の結果
+    fn type_of(&self) -> &'static str {
+        match self {
+            Value::Boolean(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Undefined => "undefined",
+            Value::Object(_) => "object",
+            Value::Closure { .. } | Value::RecClosure { .. } => "function",
+        }
+    }
+
+    /// `===` 演算子の結果 (オブジェクトと関数は同じものであるときに限り等しい)
+    fn strict_equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Boolean(b1), Value::Boolean(b2)) => b1 == b2,
+            (Value::Number(n1), Value::Number(n2)) => n1 == n2,
+            (Value::String(s1), Value::String(s2)) => s1 == s2,
+            (Value::Undefined, Value::Undefined) => true,
+            (Value::Object(props1), Value::Object(props2)) => Rc::ptr_eq(props1, props2),
+            (
+                Value::Closure {
+                    body: body1,
+                    env: env1,
+                    ..
+                },
+                Value::Closure {
+                    body: body2,
+                    env: env2,
+                    ..
+                },
+            ) => std::ptr::eq(*body1, *body2) && Rc::ptr_eq(env1, env2),
+            (
+                Value::RecClosure {
+                    funcs: funcs1,
+                    index: index1,
+                    env: env1,
+                },
+                Value::RecClosure {
+                    funcs: funcs2,
+                    index: index2,
+                    env: env2,
+                },
+            ) => std::ptr::eq(*funcs1, *funcs2) && index1 == index2 && Rc::ptr_eq(env1, env2),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Undefined => write!(f, "undefined"),
+            Value::Object(props) if props.is_empty() => write!(f, "{{}}"),
+            Value::Object(props) => {
+                write!(f, "{{ ")?;
+                for (i, (name, value)) in props.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Closure { .. } => write!(f, "[Function]"),
+            Value::RecClosure { funcs, index, .. } => {
+                write!(f, "[Function: {}]", funcs[*index].name)
+            }
+        }
+    }
+}
+
+/// 実行時エラー
+///
+/// 型検査を通ったプログラムでは起こらない
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    /// 期待した種類と異なる値 (例: `1 + true`, `1()`)
+    UnexpectedValue {
+        expected: &'static str,
+        actual: String,
+        span: Span,
+    },
+    /// 未定義の変数を参照した (例: `x`)
+    UnknownVariable { name: String, span: Span },
+    /// 存在しないプロパティを参照した (例: `{ a: 1 }.b`)
+    UnknownProperty { name: String, span: Span },
+    /// 関数呼び出しの引数の数が異なる (例: `((x: number) => x)()`)
+    ArityMismatch {
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+    /// 構文エラーのため読み飛ばした部分を評価した
+    SyntaxError { span: Span },
+}
+
+impl EvalError {
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::UnexpectedValue { span, .. }
+            | EvalError::UnknownVariable { span, .. }
+            | EvalError::UnknownProperty { span, .. }
+            | EvalError::ArityMismatch { span, .. }
+            | EvalError::SyntaxError { span } => *span,
+        }
+    }
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::UnexpectedValue {
+                expected, actual, ..
+            } => write!(f, "{} expected, but got `{}`", expected, actual),
+            EvalError::UnknownVariable { name, .. } => write!(f, "unknown variable: {}", name),
+            EvalError::UnknownProperty { name, .. } => write!(f, "unknown property: {}", name),
+            EvalError::ArityMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "wrong number of arguments: expected {}, but got {}",
+                expected, actual
+            ),
+            EvalError::SyntaxError { .. } => write!(f, "cannot evaluate a syntax error"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+pub fn eval(t: &Term) -> Result<Value<'_>, EvalError> {
+    let mut env = Env::new();
+    // `undefined` は予約語ではなく、大域変数として定義されている
+    env.insert("undefined".to_owned(), Value::Undefined);
+    eval_in(t, &env)
+}
+
+fn eval_in<'a>(t: &'a Term, env: &Env<'a>) -> Result<Value<'a>, EvalError> {
+    match t {
+        Term::False(_) => Ok(Value::Boolean(false)),
+        Term::True(_) => Ok(Value::Boolean(true)),
+        Term::Number(n, _) => Ok(Value::Number(*n)),
+        Term::String(s, _) => Ok(Value::String(s.clone())),
+        Term::Add { left, right, .. } => Ok(Value::Number(
+            eval_number(left, env)? + eval_number(right, env)?,
+        )),
+        Term::StrictEq { left, right, .. } => {
+            let left = eval_in(left, env)?;
+            let right = eval_in(right, env)?;
+            Ok(Value::Boolean(left.strict_equals(&right)))
+        }
+        Term::TypeOf { expr, .. } => Ok(Value::String(eval_in(expr, env)?.type_of().to_owned())),
+        Term::If { cond, thn, els, .. } => match eval_in(cond, env)? {
+            Value::Boolean(true) => eval_in(thn, env),
+            Value::Boolean(false) => eval_in(els, env),
+            actual => Err(unexpected("boolean", &actual, cond)),
+        },
+        Term::Var { name, span } => {
+            env.get(name)
+                .cloned()
+                .ok_or_else(|| EvalError::UnknownVariable {
+                    name: name.clone(),
+                    span: *span,
+                })
+        }
+        Term::Func { params, body, .. } => Ok(Value::Closure {
+            params,
+            body,
+            env: Rc::new(env.clone()),
+        }),
+        // 型引数は実行時には影響しない
+        Term::Instantiate { func, .. } => eval_in(func, env),
+        Term::Call { func, args, span } => {
+            let (params, body, mut closure_env) = match eval_in(func, env)? {
+                Value::Closure { params, body, env } => (params, body, (*env).clone()),
+                Value::RecClosure { funcs, index, env } => {
+                    let mut rec_env = (*env).clone();
+                    bind_rec_funcs(funcs, &env, &mut rec_env);
+                    let func = &funcs[index];
+                    (&func.params[..], &func.body, rec_env)
+                }
+                actual => return Err(unexpected("function", &actual, func)),
+            };
+            if params.len() != args.len() {
+                return Err(EvalError::ArityMismatch {
+                    expected: params.len(),
+                    actual: args.len(),
+                    span: *span,
+                });
+            }
+            for (param, arg) in params.iter().zip(args) {
+                closure_env.insert(param.name.clone(), eval_in(arg, env)?);
+            }
+            eval_in(body, &closure_env)
+        }
+        Term::Object { props, .. } => {
+            let mut values: Vec<(String, Value)> = vec![];
+            for (name, term) in props {
+                let value = eval_in(term, env)?;
+                match values.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, v)) => *v = value,
+                    None => values.push((name.clone(), value)),
+                }
+            }
+            Ok(Value::Object(Rc::new(values)))
+        }
+        Term::Get { obj, name, span } => match eval_in(obj, env)? {
+            Value::Object(props) => props
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| EvalError::UnknownProperty {
+                    name: name.clone(),
+                    span: *span,
+                }),
+            actual => Err(unexpected("object", &actual, obj)),
+        },
+        Term::Seq { body, rest, .. } => {
+            eval_in(body, env)?;
+            eval_in(rest, env)
+        }
+        Term::Const {
+            name, init, rest, ..
+        } => {
+            let value = eval_in(init, env)?;
+            let mut new_env = env.clone();
+            new_env.insert(name.clone(), value);
+            eval_in(rest, &new_env)
+        }
+        Term::RecFunc { funcs, rest, .. } => {
+            let mut new_env = env.clone();
+            bind_rec_funcs(funcs, &Rc::new(env.clone()), &mut new_env);
+            eval_in(rest, &new_env)
+        }
+        Term::TypeDecls { rest, .. } => eval_in(rest, env),
+        Term::Error { span } => Err(EvalError::SyntaxError { span: *span }),
+    }
+}
+
+/// 同じ並びで宣言された関数を、宣言された時点の環境 `env` とともに `new_env` に加える
+fn bind_rec_funcs<'a>(funcs: &'a [FuncDecl], env: &Rc<Env<'a>>, new_env: &mut Env<'a>) {
+    for (index, func) in funcs.iter().enumerate() {
+        new_env.insert(
+            func.name.clone(),
+            Value::RecClosure {
+                funcs,
+                index,
+                env: Rc::clone(env),
+            },
+        );
+    }
+}
+
+fn eval_number<'a>(t: &'a Term, env: &Env<'a>) -> Result<f64, EvalError> {
+    match eval_in(t, env)? {
+        Value::Number(n) => Ok(n),
+        actual => Err(unexpected("number", &actual, t)),
+    }
+}
+
+fn unexpected(expected: &'static str, actual: &Value, t: &Term) -> EvalError {
+    EvalError::UnexpectedValue {
+        expected,
+        actual: actual.to_string(),
+        span: t.span(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tiny_ts::basic::{Type, parse, typecheck};
+
+    /// 値が型に合っているか (関数は引数と戻り値までは確かめない)
+    ///
+    /// オブジェクトは型にないプロパティを持っていてもよい
+    fn has_type(value: &Value, typ: &Type) -> bool {
+        match (value, typ) {
+            (_, Type::Union(types)) => types.iter().any(|typ| has_type(value, typ)),
+            (Value::Object(values), Type::Object { props }) => props.iter().all(|prop| {
+                values
+                    .iter()
+                    .any(|(name, value)| name == &prop.name && has_type(value, &prop.typ))
+            }),
+            _ => matches!(
+                (value, typ),
+                (Value::Boolean(_), Type::Boolean)
+                    | (Value::Number(_), Type::Number)
+                    | (Value::String(_), Type::String)
+                    | (Value::Undefined, Type::Undefined)
+                    | (
+                        Value::Closure { .. } | Value::RecClosure { .. },
+                        Type::Func { .. }
+                    )
+            ),
+        }
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(eval(&parse("1 + 2;").unwrap()), Ok(Value::Number(3.0)));
+        assert_eq!(
+            eval(&parse("const x = 1; const y = x + 2; false ? x : y;").unwrap()),
+            Ok(Value::Number(3.0))
+        );
+        assert_eq!(
+            eval(&parse("const f = () => 1; f;").unwrap()).map(|v| v.to_string()),
+            Ok("[Function]".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_eval_closure() {
+        // 定義した時点の環境を捕捉する
+        let source = "
+            const x = 1;
+            const f = () => x;
+            const x = 2;
+            f() + x;
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(3.0)));
+
+        let source = "
+            const x = 5;
+            const f = () => () => x + 10;
+            const x = true;
+            const g = f();
+            g();
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(15.0)));
+
+        let source = "
+            const add = (x: number) => (y: number) => x + y;
+            const inc = add(1);
+            const x = 100;
+            inc(2) + add(x)(3);
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(106.0)));
+    }
+
+    #[test]
+    fn test_eval_rec_func() {
+        let source = "
+            function count(a: boolean, b: boolean): number {
+                return a ? count(false, b) + 1 : b ? count(false, false) + 10 : 0;
+            }
+            count(true, true);
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(11.0)));
+
+        let source = "
+            const base = 100;
+            function even(b: boolean): number { return b ? odd(false) + 1 : base; }
+            function odd(b: boolean): number { return b ? even(false) : 2; }
+            const base = 0;
+            odd(true);
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(100.0)));
+
+        let source = "function f(): number { return 1; } f;";
+        assert_eq!(
+            eval(&parse(source).unwrap()).map(|v| v.to_string()),
+            Ok("[Function: f]".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let err = eval(&parse("const x = 1; x();").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "function expected, but got `1`");
+        assert_eq!(err.span().range(), 13..14);
+
+        let err = eval(&parse("const x = 1; x ? 1 : 2;").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "boolean expected, but got `1`");
+
+        let err = eval(&parse("y;").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown variable: y");
+
+        let err = eval(&parse("const f = (x: number) => x; f();").unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wrong number of arguments: expected 1, but got 0"
+        );
+
+        let err = eval(&parse("const o = { a: 1 }; o.b;").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown property: b");
+        assert_eq!(err.span().range(), 20..23);
+
+        let err = eval(&parse("const x = 1; x.a;").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "object expected, but got `1`");
+    }
+
+    #[test]
+    fn test_eval_typeof() {
+        let eval_str = |source: &str| eval(&parse(source).unwrap()).map(|v| v.to_string());
+        assert_eq!(eval_str("typeof 1;"), Ok("\"number\"".to_owned()));
+        assert_eq!(eval_str("typeof true;"), Ok("\"boolean\"".to_owned()));
+        assert_eq!(eval_str("typeof 'a';"), Ok("\"string\"".to_owned()));
+        assert_eq!(eval_str("typeof {};"), Ok("\"object\"".to_owned()));
+        assert_eq!(eval_str("typeof (() => 1);"), Ok("\"function\"".to_owned()));
+        assert_eq!(
+            eval_str("typeof undefined;"),
+            Ok("\"undefined\"".to_owned())
+        );
+        assert_eq!(
+            eval_str("const f = (x: number | boolean) => typeof x === 'number' ? x + 1 : x; f(1);"),
+            Ok("2".to_owned())
+        );
+        assert_eq!(
+            eval_str("({ a: 'x', b: typeof typeof 1 });"),
+            Ok("{ a: \"x\", b: \"string\" }".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_eval_strict_eq() {
+        let eval_bool = |source: &str| match eval(&parse(source).unwrap()) {
+            Ok(Value::Boolean(b)) => b,
+            v => panic!("{}: boolean expected, but got {:?}", source, v),
+        };
+        assert!(eval_bool("1 + 1 === 2;"));
+        assert!(!eval_bool("'a' === 'b';"));
+        assert!(!eval_bool("1 === true;"));
+        assert!(eval_bool("1 === 1 === true;"));
+        assert!(eval_bool("undefined === undefined;"));
+        assert!(!eval_bool("({}) === undefined;"));
+
+        // オブジェクトと関数は同じものどうしでなければ等しくない
+        assert!(!eval_bool("({ a: 1 }) === { a: 1 };"));
+        assert!(eval_bool("const o = { a: 1 }; const p = o; o === p;"));
+        assert!(eval_bool("const o = { a: { b: 1 } }; o.a === o.a;"));
+        assert!(eval_bool("const f = () => 1; f === f;"));
+        assert!(!eval_bool("const f = () => () => 1; f() === f();"));
+        assert!(!eval_bool(
+            "function f(b: boolean): boolean { return b ? f(false) : f === g; } function g(): boolean { return true; } f(true);"
+        ));
+        assert!(eval_bool(
+            "function f(b: boolean): boolean { return b ? f(false) : (() => f)() === f; } f(true);"
+        ));
+    }
+
+    #[test]
+    fn test_eval_object() {
+        assert_eq!(
+            eval(&parse("const o = { a: 1, b: { c: true } }; o.b.c ? o.a : 0;").unwrap()),
+            Ok(Value::Number(1.0))
+        );
+        assert_eq!(
+            eval(&parse("({ a: 1 + 2, b: { c: true }, d: {} });").unwrap()).map(|v| v.to_string()),
+            Ok("{ a: 3, b: { c: true }, d: {} }".to_owned())
+        );
+        // 同じ名前のプロパティは後に書いたものが優先される
+        assert_eq!(
+            eval(&parse("({ a: 1, a: 2 }).a;").unwrap()),
+            Ok(Value::Number(2.0))
+        );
+    }
+
+    #[test]
+    fn test_eval_recursive_type() {
+        let source = "
+            type List = { head: number; tail: List } | undefined;
+            function sum(l: List): number {
+                return typeof l === 'undefined' ? 0 : l.head + sum(l.tail);
+            }
+            sum({ head: 1, tail: { head: 2, tail: { head: 3, tail: undefined } } });
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(6.0)));
+
+        assert_eq!(
+            eval(&parse("({ head: 1, tail: undefined });").unwrap()).map(|v| v.to_string()),
+            Ok("{ head: 1, tail: undefined }".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_eval_generic() {
+        let source = "
+            const compose = <A, B, C>(f: (x: A) => B, g: (y: B) => C) => (x: A) => g(f(x));
+            const inc = (x: number) => x + 1;
+            compose<number, number, number>(inc, inc)(1);
+        ";
+        assert_eq!(eval(&parse(source).unwrap()), Ok(Value::Number(3.0)));
+
+        assert_eq!(
+            eval(&parse("const id = <T>(x: T) => x; id<string>;").unwrap()).map(|v| v.to_string()),
+            Ok("[Function]".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_well_typed_programs_do_not_go_wrong() {
+        let sources = [
+            "1 + 2;",
+            "((x: number) => x + 1)(2) + (1 + 2);",
+            "function f(b: boolean): number { return b ? f(false) + 1 : 0; } f(true);",
+            "function f(): () => number { return () => g(); } function g(): number { return 1; } f();",
+            "true ? false : true;",
+            "const x = 1; const y = x + 2; false ? x : y;",
+            "const f = () => 1; f() + f();",
+            "const f = () => () => true; f();",
+            "const x = 1; const f = () => x; const x = false; f() + 2;",
+            "const f = (x: number, y: boolean) => y ? x : 0; f;",
+            "const f = (x: number, y: boolean) => y ? x : 0; f(1, false) + f(2, true);",
+            "const apply = (f: (x: number) => number, x: number) => f(x); apply((x: number) => x + 1, 2);",
+            "const o = { a: 1, b: { c: true } }; o;",
+            "const o = { a: 1, b: { c: true } }; o.b.c ? o.a : 0;",
+            "const get = (o: { x: number; y: boolean }) => o.x; get({ y: true, x: 2 });",
+            "const get = (o: { x: number }) => o; get({ y: true, x: 2 });",
+            "const o = true ? { a: 1, b: true } : { a: 2, c: 3 }; o.a;",
+            "false ? 1 : 'a';",
+            "const f = (x: number | boolean) => typeof x === 'number' ? x + 1 : x; f(1);",
+            "const f = (x: number | boolean) => typeof x === 'number' ? x + 1 : x; f(false);",
+            "typeof { a: 1 } === 'object' ? typeof ((x: number) => x) : 0;",
+            "type P = { x: number }; const f = (p: P): P => p; f({ x: 1, y: 2 }).x;",
+            "undefined;",
+            "const id = <T>(x: T) => x; id<{ a: number }>({ a: 1, b: true });",
+            "const apply = <T, U>(f: (x: T) => U, x: T) => f(x); apply<number, boolean>((x: number) => true, 1);",
+            "const apply = <T, U>(f: (x: T) => U, x: T) => f(x); apply((x: number) => true, 1);",
+            "const apply = <T, U>(f: (x: T) => U, x: T) => f(x); apply((x) => x === 1, 1);",
+            "const call = (o: { f: (x: number) => number }) => o.f(2); call({ f: (x) => x + 1 });",
+            "const pick = <T>(b: boolean, x: T, y: T) => b ? x : y; pick(false, { a: 1, b: 2 }, { a: 3 });",
+            "type L = { head: number; tail: L } | undefined; const f = (l: L) => typeof l === 'object' ? l.head : 0; f({ head: 1, tail: undefined });",
+        ];
+        for source in sources {
+            let term = parse(source).unwrap();
+            let typ = typecheck(&term).unwrap();
+            let value = eval(&term).unwrap();
+            assert!(
+                has_type(&value, &typ),
+                "{}: {} is not {}",
+                source,
+                value,
+                typ
+            );
+        }
+    }
+}
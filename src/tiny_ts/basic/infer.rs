@@ -0,0 +1,1015 @@
+use std::collections::HashMap;
+
+use crate::tiny_ts::basic::Term;
+use crate::tiny_ts::basic::resolve::{Resolve, TypeNames};
+use crate::tiny_ts::basic::term;
+use crate::tiny_ts::basic::typ::{Param, Prop, Type, TypeParam};
+use crate::tiny_ts::basic::typecheck::{TypeError, last_statement, occurs, subst};
+use crate::tiny_ts::span::Span;
+
+/// 型スキーム (`vars` の型変数について多相な型)
+///
+/// 変数を参照するたびに `vars` を新しい型変数に置き換える (例: `const id = (x) => x;` の `id`)
+#[derive(Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    /// `vars` のうち、ジェネリック関数の型引数として宣言したもの (宣言の順)
+    ///
+    /// 型引数を指定した場合 (例: `f<number>`) は、これらだけを指定した型に置き換える
+    type_params: Vec<usize>,
+    typ: Type,
+}
+
+impl Scheme {
+    /// 多相でない型
+    fn mono(typ: Type) -> Self {
+        Self {
+            vars: vec![],
+            type_params: vec![],
+            typ,
+        }
+    }
+}
+
+/// 型環境
+#[derive(Clone, Default)]
+struct TypeEnv {
+    /// 変数名から型スキームへの対応
+    vars: HashMap<String, Scheme>,
+    /// 見えている型エイリアスと、型引数を表す型変数
+    types: TypeNames,
+}
+
+/// 型変数の状態
+#[derive(Clone)]
+enum VarState {
+    /// まだ型が決まっていない
+    Unbound,
+    /// 単一化によって型が決まった
+    Bound(Type),
+    /// 型引数として宣言されたもの (名前はその型引数の名前) で、他の型に決まることはない
+    Rigid(String),
+}
+
+/// 単一化に失敗した理由
+enum UnifyError {
+    /// 構造の異なる型どうしを単一化しようとした
+    Mismatch,
+    /// 型変数 `var` を、それ自身を含む型 `typ` と単一化しようとした (出現検査)
+    Occurs { var: Type, typ: Type },
+}
+
+pub fn infer(t: &Term) -> Result<Type, TypeError> {
+    let (typ, errors) = infer_all(t);
+    match errors.into_iter().next() {
+        None => Ok(typ),
+        Some(e) => Err(e),
+    }
+}
+
+/// 型注釈のない引数の型を推論しながら型検査し、推論した型と全ての型エラーを返す
+///
+/// `typecheck_all` と異なり部分型関係や型の絞り込みは扱わず、型が等しいという制約を単一化で解く。
+/// `const` や `function` で定義した変数の型は、環境に現れない型変数について多相にする (let 多相)。
+/// 結果の型に残った型変数は `A`, `B`, ... と名付け、関数型であればジェネリック関数の型にする
+pub fn infer_all(t: &Term) -> (Type, Vec<TypeError>) {
+    let mut inferer = Inferer {
+        errors: vec![],
+        vars: vec![],
+        aliases: vec![],
+    };
+    let mut env = TypeEnv::default();
+    // 型検査と同じく、`undefined` は型が `undefined` の大域変数として扱う
+    env.vars
+        .insert("undefined".to_owned(), Scheme::mono(Type::Undefined));
+    let typ = inferer.infer(t, &env);
+    let typ = inferer.finish(&typ);
+    let errors = std::mem::take(&mut inferer.errors)
+        .into_iter()
+        .map(|error| inferer.finish_error(error))
+        .collect();
+    (typ, errors)
+}
+
+struct Inferer {
+    errors: Vec<TypeError>,
+    /// 型変数 (`Type::Var` の `id` 番目) の状態
+    vars: Vec<VarState>,
+    /// 型エイリアスが別名を付けた型 (`Type::Alias` の `id` 番目)
+    aliases: Vec<Type>,
+}
+
+impl Inferer {
+    fn infer(&mut self, t: &Term, env: &TypeEnv) -> Type {
+        match t {
+            Term::False(_) | Term::True(_) => Type::Boolean,
+            Term::Number(_, _) => Type::Number,
+            Term::String(_, _) => Type::String,
+            Term::Add { left, right, .. } => {
+                self.expect_type(left, &Type::Number, env);
+                self.expect_type(right, &Type::Number, env);
+                Type::Number
+            }
+            Term::StrictEq { left, right, .. } => {
+                self.infer(left, env);
+                self.infer(right, env);
+                Type::Boolean
+            }
+            Term::TypeOf { expr, .. } => {
+                self.infer(expr, env);
+                Type::String
+            }
+            Term::If { cond, thn, els, .. } => {
+                let cond_type = self.infer(cond, env);
+                self.unify_or(&Type::Boolean, &cond_type, cond.span(), |_, actual| {
+                    TypeError::NonBooleanCondition {
+                        actual,
+                        span: cond.span(),
+                    }
+                });
+                let thn_type = self.infer(thn, env);
+                let els_type = self.infer(els, env);
+                self.unify_or(&thn_type, &els_type, els.span(), |expected, actual| {
+                    TypeError::TypeMismatch {
+                        expected,
+                        actual,
+                        span: els.span(),
+                    }
+                });
+                thn_type
+            }
+            Term::Var { name, span } => match env.vars.get(name) {
+                Some(scheme) => self.instantiate(scheme),
+                None => {
+                    self.errors.push(TypeError::UnknownVariable {
+                        name: name.clone(),
+                        span: *span,
+                    });
+                    Type::Unknown
+                }
+            },
+            Term::Func { .. } => self.infer_func(t, env).0,
+            Term::Call { func, args, span } => {
+                let func_type = self.infer(func, env);
+                let arg_types = args
+                    .iter()
+                    .map(|arg| self.infer(arg, env))
+                    .collect::<Vec<_>>();
+                let callee = match self.expand(&func_type) {
+                    // 型の決まっていない関数は、引数の数に合わせた関数型とする
+                    Type::Var { id, .. } if self.is_unbound(id) => {
+                        let params = (0..args.len())
+                            .map(|i| Param {
+                                name: format!("arg{}", i),
+                                typ: self.fresh_var(),
+                            })
+                            .collect();
+                        let callee = Type::Func {
+                            params,
+                            ret_type: Box::new(self.fresh_var()),
+                        };
+                        self.vars[id] = VarState::Bound(callee.clone());
+                        callee
+                    }
+                    callee => callee,
+                };
+                match callee {
+                    Type::Func { params, ret_type } => {
+                        if params.len() != args.len() {
+                            self.errors.push(TypeError::ArityMismatch {
+                                expected: params.len(),
+                                actual: args.len(),
+                                span: *span,
+                            });
+                        }
+                        for ((arg, arg_type), param) in args.iter().zip(&arg_types).zip(&params) {
+                            self.unify_or(&param.typ, arg_type, arg.span(), |expected, actual| {
+                                TypeError::TypeMismatch {
+                                    expected,
+                                    actual,
+                                    span: arg.span(),
+                                }
+                            });
+                        }
+                        *ret_type
+                    }
+                    Type::Unknown => Type::Unknown,
+                    _ => {
+                        self.errors.push(TypeError::NotCallable {
+                            actual: self.zonk(&func_type),
+                            span: func.span(),
+                        });
+                        Type::Unknown
+                    }
+                }
+            }
+            Term::Instantiate {
+                func,
+                type_args,
+                span,
+            } => {
+                let type_args = type_args
+                    .iter()
+                    .map(|(typ, span)| self.resolve(typ, *span, &env.types))
+                    .collect::<Vec<_>>();
+                let scheme = match func.as_ref() {
+                    Term::Var { name, .. } => env.vars.get(name),
+                    _ => None,
+                };
+                match scheme {
+                    Some(scheme) if scheme.type_params.len() == type_args.len() => {
+                        // 宣言した型引数は指定した型に、それ以外 (型注釈のない引数など) は新しい型変数に置き換える
+                        let map = scheme
+                            .vars
+                            .iter()
+                            .map(|&id| {
+                                let typ = match scheme.type_params.iter().position(|&p| p == id) {
+                                    Some(i) => type_args[i].clone(),
+                                    None => self.fresh_var(),
+                                };
+                                (id, typ)
+                            })
+                            .collect::<Vec<_>>();
+                        subst(&scheme.typ, &map, &mut |name| self.fresh_type_param(name))
+                    }
+                    _ => {
+                        let expected = scheme.map_or(0, |scheme| scheme.type_params.len());
+                        if self.infer(func, env) != Type::Unknown {
+                            self.errors.push(TypeError::TypeArityMismatch {
+                                expected,
+                                actual: type_args.len(),
+                                span: *span,
+                            });
+                        }
+                        Type::Unknown
+                    }
+                }
+            }
+            Term::Object { props, .. } => {
+                let mut prop_types: Vec<Prop> = vec![];
+                for (name, term) in props {
+                    let typ = self.infer(term, env);
+                    // 同じ名前のプロパティは後に書いたものが優先される
+                    match prop_types.iter_mut().find(|prop| &prop.name == name) {
+                        Some(prop) => prop.typ = typ,
+                        None => prop_types.push(Prop {
+                            name: name.clone(),
+                            typ,
+                        }),
+                    }
+                }
+                Type::Object { props: prop_types }
+            }
+            Term::Get { obj, name, span } => {
+                let obj_type = self.infer(obj, env);
+                let prop_type = match self.expand(&obj_type) {
+                    Type::Object { props } => props
+                        .into_iter()
+                        .find(|prop| &prop.name == name)
+                        .map(|prop| prop.typ),
+                    // どのプロパティを持つかわからない型からは推論しない
+                    Type::Var { id, .. } if self.is_unbound(id) => {
+                        self.errors.push(TypeError::UninferredObject {
+                            name: name.clone(),
+                            span: obj.span(),
+                        });
+                        return Type::Unknown;
+                    }
+                    Type::Unknown => Some(Type::Unknown),
+                    _ => None,
+                };
+                match prop_type {
+                    Some(typ) => typ,
+                    None => {
+                        self.errors.push(TypeError::UnknownProperty {
+                            name: name.clone(),
+                            actual: self.zonk(&obj_type),
+                            span: *span,
+                        });
+                        Type::Unknown
+                    }
+                }
+            }
+            Term::Seq { body, rest, .. } => {
+                self.infer(body, env);
+                self.infer(rest, env)
+            }
+            Term::Const {
+                name, init, rest, ..
+            } => {
+                let (typ, type_params) = self.infer_func(init, env);
+                let scheme = Scheme {
+                    type_params,
+                    ..self.generalize(&typ, env)
+                };
+                let mut new_env = env.clone();
+                new_env.vars.insert(name.clone(), scheme);
+                self.infer(rest, &new_env)
+            }
+            Term::RecFunc { funcs, rest, .. } => {
+                // 互いに再帰する関数の本体の中では、それらの関数は多相でない。
+                // 全ての本体から型変数を決めてから、それぞれの型を多相にする
+                let mut rec_env = env.clone();
+                let mut signatures = vec![];
+                for func in funcs {
+                    let (params, ret_type) = self.resolve_signature(func, &env.types);
+                    let typ = Type::Func {
+                        params: params.clone(),
+                        ret_type: Box::new(ret_type.clone()),
+                    };
+                    rec_env
+                        .vars
+                        .insert(func.name.clone(), Scheme::mono(typ.clone()));
+                    signatures.push((params, ret_type, typ));
+                }
+                let mut new_env = env.clone();
+                for (func, (params, ret_type, typ)) in funcs.iter().zip(signatures) {
+                    let mut func_env = rec_env.clone();
+                    for param in params {
+                        func_env.vars.insert(param.name, Scheme::mono(param.typ));
+                    }
+                    let body_type = self.infer(&func.body, &func_env);
+                    self.check_return_type(
+                        &body_type,
+                        &ret_type,
+                        last_statement(&func.body).span(),
+                        func.ret_type.1,
+                    );
+                    new_env
+                        .vars
+                        .insert(func.name.clone(), self.generalize(&typ, env));
+                }
+                self.infer(rest, &new_env)
+            }
+            Term::TypeDecls { decls, rest, .. } => {
+                let mut new_env = env.clone();
+                self.declare_type_aliases(decls, &mut new_env.types);
+                self.infer(rest, &new_env)
+            }
+            Term::Error { .. } => Type::Unknown,
+        }
+    }
+
+    /// 無名関数 `t` の型と、宣言した型引数を表す型変数の `id` の並びを求める
+    ///
+    /// `t` が無名関数でなければ、型引数はないものとしてその型を求める
+    fn infer_func(&mut self, t: &Term, env: &TypeEnv) -> (Type, Vec<usize>) {
+        let Term::Func {
+            type_params,
+            params,
+            ret_type,
+            body,
+            ..
+        } = t
+        else {
+            return (self.infer(t, env), vec![]);
+        };
+        let mut new_env = env.clone();
+        let mut type_param_ids = vec![];
+        for type_param in type_params {
+            let TypeParam { name, id } = self.fresh_type_param(&type_param.name);
+            type_param_ids.push(id);
+            new_env.types.insert(name.clone(), Type::Var { name, id });
+        }
+        let params = params
+            .iter()
+            .map(|param| self.resolve_param(param, &new_env.types))
+            .collect::<Vec<_>>();
+        for param in &params {
+            new_env
+                .vars
+                .insert(param.name.clone(), Scheme::mono(param.typ.clone()));
+        }
+        let body_type = self.infer(body, &new_env);
+        let ret_type = match ret_type {
+            Some((ret_type, annotation_span)) => {
+                let ret_type = self.resolve(ret_type, *annotation_span, &new_env.types);
+                self.check_return_type(&body_type, &ret_type, body.span(), *annotation_span);
+                ret_type
+            }
+            None => body_type,
+        };
+        let func = Type::Func {
+            params,
+            ret_type: Box::new(ret_type),
+        };
+        (func, type_param_ids)
+    }
+
+    fn expect_type(&mut self, t: &Term, expected: &Type, env: &TypeEnv) {
+        let actual = self.infer(t, env);
+        self.unify_or(expected, &actual, t.span(), |expected, actual| {
+            TypeError::TypeMismatch {
+                expected,
+                actual,
+                span: t.span(),
+            }
+        });
+    }
+
+    /// 関数の本体の型を戻り値の型注釈と単一化する
+    fn check_return_type(
+        &mut self,
+        body_type: &Type,
+        ret_type: &Type,
+        span: Span,
+        annotation_span: Span,
+    ) {
+        self.unify_or(ret_type, body_type, span, |expected, actual| {
+            TypeError::ReturnTypeMismatch {
+                expected,
+                actual,
+                span,
+                annotation_span,
+            }
+        });
+    }
+
+    /// `expected` と `actual` を単一化する
+    ///
+    /// 単一化できない場合は、型変数を置き換えた `expected` と `actual` から `mismatch` で作ったエラーを報告する。
+    /// 出現検査で失敗した場合は `span` の位置で型を推論できないことを報告する
+    fn unify_or(
+        &mut self,
+        expected: &Type,
+        actual: &Type,
+        span: Span,
+        mismatch: impl FnOnce(Type, Type) -> TypeError,
+    ) {
+        match self.unify(expected, actual, &mut vec![]) {
+            Ok(()) => {}
+            Err(UnifyError::Mismatch) => {
+                let e = mismatch(self.zonk(expected), self.zonk(actual));
+                self.errors.push(e);
+            }
+            Err(UnifyError::Occurs { var, typ }) => {
+                self.errors.push(TypeError::InfiniteType {
+                    var: Box::new(var),
+                    typ: Box::new(self.zonk(&typ)),
+                    span,
+                });
+            }
+        }
+    }
+
+    /// `t1` と `t2` が等しくなるように型変数の型を決める
+    ///
+    /// - 型の決まっていない型変数は、それ自身を含まない型であればその型に決める
+    /// - 関数型は引数の数が等しく、引数と戻り値の型をそれぞれ単一化できればよい
+    /// - オブジェクト型は同じ名前のプロパティを持ち、それぞれの型を単一化できればよい
+    /// - 合併型は同じ型からなるものどうしだけを等しいとみなす
+    /// - 型エイリアスは展開して比べる (`assumed` は展開している途中の型エイリアスの組)
+    fn unify(
+        &mut self,
+        t1: &Type,
+        t2: &Type,
+        assumed: &mut Vec<(Type, Type)>,
+    ) -> Result<(), UnifyError> {
+        let t1 = self.prune(t1);
+        let t2 = self.prune(t2);
+        match (&t1, &t2) {
+            (Type::Var { id: id1, .. }, Type::Var { id: id2, .. }) if id1 == id2 => Ok(()),
+            // 両方とも型が決まっていなければ、後に作った型変数を先に作った型変数に決める
+            (Type::Var { id: id1, .. }, Type::Var { id: id2, .. })
+                if self.is_unbound(*id1) && self.is_unbound(*id2) =>
+            {
+                let (older, newer) = if id1 < id2 { (&t1, *id2) } else { (&t2, *id1) };
+                self.vars[newer] = VarState::Bound(older.clone());
+                Ok(())
+            }
+            (var @ Type::Var { id, .. }, typ) | (typ, var @ Type::Var { id, .. })
+                if self.is_unbound(*id) =>
+            {
+                if occurs(*id, &self.zonk(typ)) {
+                    return Err(UnifyError::Occurs {
+                        var: var.clone(),
+                        typ: typ.clone(),
+                    });
+                }
+                self.vars[*id] = VarState::Bound(typ.clone());
+                Ok(())
+            }
+            (Type::Unknown, _) | (_, Type::Unknown) => Ok(()),
+            _ if t1 == t2 => Ok(()),
+            (Type::Alias { .. }, _) | (_, Type::Alias { .. }) => {
+                let pair = (t1.clone(), t2.clone());
+                if assumed.contains(&pair) {
+                    return Ok(());
+                }
+                assumed.push(pair);
+                let (expanded1, expanded2) = (self.expand(&t1), self.expand(&t2));
+                let result = self.unify(&expanded1, &expanded2, assumed);
+                assumed.pop();
+                result
+            }
+            (
+                Type::Func {
+                    params: params1,
+                    ret_type: ret_type1,
+                },
+                Type::Func {
+                    params: params2,
+                    ret_type: ret_type2,
+                },
+            ) if params1.len() == params2.len() => {
+                for (p1, p2) in params1.iter().zip(params2) {
+                    self.unify(&p1.typ, &p2.typ, assumed)?;
+                }
+                self.unify(ret_type1, ret_type2, assumed)
+            }
+            (Type::Object { props: props1 }, Type::Object { props: props2 })
+                if props1.len() == props2.len() =>
+            {
+                for p1 in props1 {
+                    let Some(p2) = props2.iter().find(|p2| p2.name == p1.name) else {
+                        return Err(UnifyError::Mismatch);
+                    };
+                    self.unify(&p1.typ, &p2.typ, assumed)?;
+                }
+                Ok(())
+            }
+            (Type::Union(_), Type::Union(_)) => {
+                let (Type::Union(types1), Type::Union(types2)) = (self.zonk(&t1), self.zonk(&t2))
+                else {
+                    return Err(UnifyError::Mismatch);
+                };
+                if types1.len() == types2.len() && types1.iter().all(|typ| types2.contains(typ)) {
+                    Ok(())
+                } else {
+                    Err(UnifyError::Mismatch)
+                }
+            }
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn new_var(&mut self, name: String, state: VarState) -> Type {
+        let id = self.vars.len();
+        self.vars.push(state);
+        Type::Var { name, id }
+    }
+
+    /// 型の決まっていない新しい型変数
+    fn fresh_var(&mut self) -> Type {
+        let name = format!("T{}", self.vars.len());
+        self.new_var(name, VarState::Unbound)
+    }
+
+    /// 型引数として宣言する、他の型に決まることのない新しい型変数
+    fn fresh_type_param(&mut self, name: &str) -> TypeParam {
+        let id = self.vars.len();
+        self.vars.push(VarState::Rigid(name.to_owned()));
+        TypeParam {
+            name: name.to_owned(),
+            id,
+        }
+    }
+
+    fn is_unbound(&self, id: usize) -> bool {
+        matches!(self.vars[id], VarState::Unbound)
+    }
+
+    /// 型の決まった型変数であれば、決まった型まで辿る
+    fn prune(&self, typ: &Type) -> Type {
+        let mut typ = typ;
+        while let Type::Var { id, .. } = typ {
+            match &self.vars[*id] {
+                VarState::Bound(bound) => typ = bound,
+                VarState::Unbound | VarState::Rigid(_) => break,
+            }
+        }
+        typ.clone()
+    }
+
+    /// 型の決まった型変数や型エイリアスであれば、最も外側の型が決まるまで辿る
+    fn expand(&self, typ: &Type) -> Type {
+        let mut typ = self.prune(typ);
+        while let Type::Alias { id, .. } = typ {
+            typ = self.prune(&self.aliases[id]);
+        }
+        typ
+    }
+
+    /// `typ` の中の型の決まった型変数を全て決まった型に置き換える
+    fn zonk(&self, typ: &Type) -> Type {
+        match typ {
+            Type::Var { id, .. } => match &self.vars[*id] {
+                VarState::Bound(bound) => self.zonk(bound),
+                VarState::Unbound | VarState::Rigid(_) => typ.clone(),
+            },
+            Type::Func { params, ret_type } => Type::Func {
+                params: params
+                    .iter()
+                    .map(|param| Param {
+                        name: param.name.clone(),
+                        typ: self.zonk(&param.typ),
+                    })
+                    .collect(),
+                ret_type: Box::new(self.zonk(ret_type)),
+            },
+            Type::Generic { type_params, func } => Type::Generic {
+                type_params: type_params.clone(),
+                func: Box::new(self.zonk(func)),
+            },
+            Type::Object { props } => Type::Object {
+                props: props
+                    .iter()
+                    .map(|prop| Prop {
+                        name: prop.name.clone(),
+                        typ: self.zonk(&prop.typ),
+                    })
+                    .collect(),
+            },
+            Type::Union(types) => Type::union(types.iter().map(|typ| self.zonk(typ))),
+            Type::Boolean
+            | Type::Number
+            | Type::String
+            | Type::Undefined
+            | Type::Ref(_)
+            | Type::Alias { .. }
+            | Type::Unknown => typ.clone(),
+        }
+    }
+
+    /// 型スキームの型変数を新しい型変数に置き換える
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let map = scheme
+            .vars
+            .iter()
+            .map(|&id| (id, self.fresh_var()))
+            .collect::<Vec<_>>();
+        subst(&scheme.typ, &map, &mut |name| self.fresh_type_param(name))
+    }
+
+    /// `typ` に現れる型変数のうち、`env` に現れないものについて多相にする
+    fn generalize(&self, typ: &Type, env: &TypeEnv) -> Scheme {
+        let mut env_vars = vec![];
+        for scheme in env.vars.values() {
+            let mut vars = vec![];
+            free_vars(&self.zonk(&scheme.typ), &mut vars);
+            env_vars.extend(vars.into_iter().filter(|id| !scheme.vars.contains(id)));
+        }
+        for typ in env.types.values() {
+            free_vars(&self.zonk(typ), &mut env_vars);
+        }
+        let typ = self.zonk(typ);
+        let mut vars = vec![];
+        free_vars(&typ, &mut vars);
+        vars.retain(|id| !env_vars.contains(id));
+        vars.sort();
+        Scheme {
+            vars,
+            type_params: vec![],
+            typ,
+        }
+    }
+
+    /// 推論した型の型変数を決まった型に置き換え、残った型変数に作った順に `A`, `B`, ... と名前を付ける
+    ///
+    /// 型変数が残った関数型はジェネリック関数の型にする
+    fn finish(&mut self, typ: &Type) -> Type {
+        let typ = self.zonk(typ);
+        let mut vars = vec![];
+        free_vars(&typ, &mut vars);
+        if vars.is_empty() {
+            return typ;
+        }
+        vars.sort();
+        let type_params = vars
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| TypeParam {
+                name: var_name(i),
+                id,
+            })
+            .collect::<Vec<_>>();
+        let map = type_params
+            .iter()
+            .map(|param| {
+                let var = Type::Var {
+                    name: param.name.clone(),
+                    id: param.id,
+                };
+                (param.id, var)
+            })
+            .collect::<Vec<_>>();
+        match subst(&typ, &map, &mut |name| self.fresh_type_param(name)) {
+            func @ Type::Func { .. } => Type::Generic {
+                type_params,
+                func: Box::new(func),
+            },
+            typ => typ,
+        }
+    }
+
+    /// 型エラーに含まれる型の型変数を決まった型に置き換え、残った型変数に作った順に `A`, `B`, ... と名前を付ける
+    ///
+    /// 宣言した型引数はその名前のままとし、同じ名前は付けない
+    fn finish_error(&mut self, mut error: TypeError) -> TypeError {
+        let mut types = error.types_mut();
+        let mut vars = vec![];
+        for typ in types.iter_mut() {
+            **typ = self.zonk(typ);
+            free_vars(typ, &mut vars);
+        }
+        vars.sort();
+        let declared = vars
+            .iter()
+            .filter_map(|&id| match &self.vars[id] {
+                VarState::Rigid(name) => Some(name.clone()),
+                VarState::Unbound | VarState::Bound(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let mut names = (0..).map(var_name).filter(|name| !declared.contains(name));
+        let map = vars
+            .into_iter()
+            .filter(|&id| self.is_unbound(id))
+            .map(|id| {
+                let name = names.next().unwrap();
+                (id, Type::Var { name, id })
+            })
+            .collect::<Vec<_>>();
+        for typ in types {
+            *typ = subst(typ, &map, &mut |name| self.fresh_type_param(name));
+        }
+        error
+    }
+}
+
+impl Resolve for Inferer {
+    fn errors(&mut self) -> &mut Vec<TypeError> {
+        &mut self.errors
+    }
+
+    fn aliases(&mut self) -> &mut Vec<Type> {
+        &mut self.aliases
+    }
+
+    /// 単一化では多相な引数を扱えないため、型引数を新しい型変数に置き換えて具体化する
+    fn resolve_generic(
+        &mut self,
+        type_params: &[TypeParam],
+        func: &Type,
+        span: Span,
+        types: &TypeNames,
+    ) -> Type {
+        let mut new_types = types.clone();
+        for type_param in type_params {
+            let var = self.fresh_var();
+            new_types.insert(type_param.name.clone(), var);
+        }
+        self.resolve(func, span, &new_types)
+    }
+
+    /// 新しい型変数とし、本体での使われ方から推論する
+    fn missing_param_type(&mut self, _param: &term::Param) -> Type {
+        self.fresh_var()
+    }
+}
+
+/// `typ` に現れる型変数の `id` を、まだ `vars` になければ加える
+fn free_vars(typ: &Type, vars: &mut Vec<usize>) {
+    match typ {
+        Type::Var { id, .. } => {
+            if !vars.contains(id) {
+                vars.push(*id);
+            }
+        }
+        Type::Func { params, ret_type } => {
+            for param in params {
+                free_vars(&param.typ, vars);
+            }
+            free_vars(ret_type, vars);
+        }
+        Type::Generic { type_params, func } => {
+            let mut inner = vec![];
+            free_vars(func, &mut inner);
+            for id in inner {
+                if type_params.iter().all(|param| param.id != id) && !vars.contains(&id) {
+                    vars.push(id);
+                }
+            }
+        }
+        Type::Object { props } => {
+            for prop in props {
+                free_vars(&prop.typ, vars);
+            }
+        }
+        Type::Union(types) => {
+            for typ in types {
+                free_vars(typ, vars);
+            }
+        }
+        Type::Boolean
+        | Type::Number
+        | Type::String
+        | Type::Undefined
+        | Type::Ref(_)
+        | Type::Alias { .. }
+        | Type::Unknown => {}
+    }
+}
+
+/// 結果の型の `i` 番目の型変数の名前 (`A`, `B`, ..., `Z`, `A1`, `B1`, ...)
+fn var_name(i: usize) -> String {
+    let letter = char::from(b'A' + (i % 26) as u8);
+    match i / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tiny_ts::basic::{eval, parse};
+
+    fn infer_str(source: &str) -> Result<String, String> {
+        match infer(&parse(source).unwrap()) {
+            Ok(typ) => Ok(typ.to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_infer() {
+        assert_eq!(
+            infer_str("(x) => x + 1"),
+            Ok("(x: number) => number".to_owned())
+        );
+        assert_eq!(
+            infer_str("(x, y) => y ? x : 1"),
+            Ok("(x: number, y: boolean) => number".to_owned())
+        );
+        assert_eq!(
+            infer_str("(x, y: string) => x ? y : 'a'"),
+            Ok("(x: boolean, y: string) => string".to_owned())
+        );
+        assert_eq!(infer_str("(x) => x"), Ok("<A>(x: A) => A".to_owned()));
+        assert_eq!(
+            infer_str("(x): number => x"),
+            Ok("(x: number) => number".to_owned())
+        );
+        assert_eq!(
+            infer_str("(f, x) => f(x)"),
+            Ok("<A, B>(f: (arg0: A) => B, x: A) => B".to_owned())
+        );
+        assert_eq!(
+            infer_str("(x) => { a: x, b: x + 1 }"),
+            Ok("(x: number) => { a: number; b: number }".to_owned())
+        );
+        assert_eq!(
+            infer_str("const f = (o: { a: number }) => o.a; f({ a: 1 });"),
+            Ok("number".to_owned())
+        );
+        assert_eq!(infer_str("undefined"), Ok("undefined".to_owned()));
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        let source = "const id = (x) => x; id(1) + (id(true) ? 1 : 2);";
+        assert_eq!(infer_str(source), Ok("number".to_owned()));
+
+        let source = "
+            const compose = (f, g) => (x) => g(f(x));
+            compose((x) => x + 1, (y) => y === 1);
+        ";
+        assert_eq!(infer_str(source), Ok("(x: number) => boolean".to_owned()));
+
+        // 関数宣言も本体を推論した後で多相になる
+        let source = "
+            function loop(x): number { return loop(x); }
+            const a = loop(true);
+            loop('a') + a;
+        ";
+        assert_eq!(infer_str(source), Ok("number".to_owned()));
+
+        // 型引数は宣言した順に指定できる
+        let source = "const pair = <T, U>(x: U, y: T) => y; pair<number, boolean>(true, 1);";
+        assert_eq!(infer_str(source), Ok("number".to_owned()));
+
+        // 型注釈のない引数の型変数は型引数として指定しない
+        let source = "const f = <T>(y: T, x) => y; f<number>(1, 2);";
+        assert_eq!(infer_str(source), Ok("number".to_owned()));
+        let source = "const f = <T>(y: T) => (x) => y; f<number>(1);";
+        assert_eq!(infer_str(source), Ok("<A>(x: A) => number".to_owned()));
+
+        // 引数は多相でない
+        let source = "(f) => f(1) + (f(true) ? 1 : 2)";
+        assert_eq!(
+            infer_str(source),
+            Err("number expected, but got boolean".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_infer_error() {
+        let err = infer(&parse("(f) => f(f)").unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "could not infer a type: A occurs in (arg0: A) => B"
+        );
+        assert_eq!(err.span().range(), 9..10);
+
+        let err = infer(&parse("(o) => o.a").unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::UninferredObject {
+                name: "a".to_owned(),
+                span: Span::new(7, 8),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "could not infer the type of the object with property a"
+        );
+        assert_eq!(err.span().range(), 7..8);
+
+        // 部分型関係は扱わない
+        assert_eq!(
+            infer_str("const f = (o: { a: number }) => o.a; f({ a: 1, b: 2 });"),
+            Err("{ a: number } expected, but got { a: number; b: number }".to_owned())
+        );
+        assert_eq!(
+            infer_str("(x) => x ? 1 : true"),
+            Err("number expected, but got boolean".to_owned())
+        );
+        assert_eq!(
+            infer_str("(x) => x(1) + x"),
+            Err("number expected, but got (arg0: number) => number".to_owned())
+        );
+        // 宣言した型引数は他の型に決まらない
+        assert_eq!(
+            infer_str("<T>(x: T) => x + 1"),
+            Err("number expected, but got T".to_owned())
+        );
+        // エラーに残った型変数には、宣言した型引数と異なる名前を付ける
+        assert_eq!(
+            infer_str("<A>(x: A, y) => x === y ? x : (z) => y"),
+            Err("A expected, but got (z: C) => B".to_owned())
+        );
+        assert_eq!(
+            infer_str("(y: number, x): boolean => x + y"),
+            Err("return type boolean expected, but the body has type number".to_owned())
+        );
+        assert_eq!(
+            infer_str("const id = <T>(x: T) => x; id<number, boolean>(1);"),
+            Err("wrong number of type arguments: expected 1, but got 2".to_owned())
+        );
+        assert_eq!(
+            infer_str("const id = (x) => x; id<number>(1);"),
+            Err("wrong number of type arguments: expected 0, but got 1".to_owned())
+        );
+        assert_eq!(
+            infer_str("type A = B; type B = A; (x: A) => x;"),
+            Err("type alias A circularly references itself".to_owned())
+        );
+
+        let (_, errors) =
+            infer_all(&parse("const f = (x) => x + 1; f(true); y; f(1)(2);").unwrap());
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            [
+                "number expected, but got boolean",
+                "unknown variable: y",
+                "function type expected, but got number",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recursive_type() {
+        let source = "
+            type Stream = { head: number; tail: () => Stream };
+            const cons = (head, tail: Stream) => { head: head, tail: () => tail };
+            (s: Stream) => cons(1, cons(2, s)).tail().head;
+        ";
+        assert_eq!(infer_str(source), Ok("(s: Stream) => number".to_owned()));
+
+        // 合併型との間に部分型関係はない
+        let source = "
+            type List = { head: number; tail: List } | undefined;
+            (tail: List, head): List => { head: head, tail: tail };
+        ";
+        assert_eq!(
+            infer_str(source),
+            Err(
+                "return type List expected, but the body has type { head: A; tail: List }"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_infer_then_eval() {
+        let source = "
+            const twice = (f) => (x) => f(f(x));
+            const add = (x) => (y) => x + y;
+            twice(add(3))(1) + (twice((b) => b ? false : true)(true) ? 10 : 20);
+        ";
+        let term = parse(source).unwrap();
+        assert_eq!(infer(&term).unwrap().to_string(), "number");
+        assert_eq!(eval(&term).unwrap().to_string(), "17");
+    }
+}
@@ -1,172 +1,748 @@
-use crate::tiny_ts::basic::Type;
-use crate::tiny_ts::basic::term::Param;
+use crate::tiny_ts::basic::term::{FuncDecl, Param, TypeDecl, TypeParam};
+use crate::tiny_ts::basic::{Type, typ};
+#[cfg(test)]
+use crate::tiny_ts::span::ClearSpans;
+use crate::tiny_ts::span::Span;
 
 use super::term::Term;
 use super::token::Token;
 
-pub fn parse(s: &str) -> Term {
-    let lexer = <Token as logos::Logos>::lexer(s);
-    read_term(&mut lexer.peekable())
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// 期待していないトークンを読み込んだ (例: `const = 1;`)
+    UnexpectedToken {
+        found: Token,
+        expected: Vec<&'static str>,
+        span: Span,
+    },
+    /// 入力が途中で終わった (例: `(x: number) =>`)
+    UnexpectedEof {
+        expected: Vec<&'static str>,
+        span: Span,
+    },
+    /// 字句解析できない文字列を読み込んだ (例: `@`)
+    InvalidToken { text: String, span: Span },
 }
 
-// func         = "paren_l" "paren_r" "arrow" ternary | "paren_l" param_list "paren_r" "arrow" ternary
-// param        = "ident" "colon" "ident"
-// param_list   = param | param "comma" param_list
-// func_call    = "ident" "paren_l" "paren_r" | "ident" "paren_l" arg_list "paren_r"
-// arg_list     = arg | arg "comma" arg_list
-// primary_expr = "false" | "true" | "integer" | "ident" | func | func_call
-// binary       = primary_expr | primary_expr "plus" binary
-// ternary      = binary | binary "question" ternary "colon" ternary
-// const        = "const" "ident" "equals" ternary "semicolon" term
-// seq          = ternary "semicolon" term
-// term         = const | seq | ternary "semicolon"
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::InvalidToken { span, .. } => *span,
+        }
+    }
+}
 
-// TODO: param support f(1)
-// TODO: param support f: (x: number) => x
+#[cfg(test)]
+impl ClearSpans for ParseError {
+    fn clear_spans(&mut self) {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::InvalidToken { span, .. } => span.clear_spans(),
+        }
+    }
+}
 
-fn read_term(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let token = iter.peek();
-    match token {
-        None => unreachable!(),
-        Some(token) => {
-            let token = token.as_ref().unwrap();
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                found, expected, ..
+            } => write!(f, "{} expected, but got `{}`", expected.join(" or "), found),
+            ParseError::UnexpectedEof { expected, .. } => {
+                write!(
+                    f,
+                    "{} expected, but got end of input",
+                    expected.join(" or ")
+                )
+            }
+            ParseError::InvalidToken { text, .. } => write!(f, "invalid token `{}`", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(s: &str) -> Result<Term, ParseError> {
+    let (term, errors) = parse_with_recovery(s);
+    match errors.into_iter().next() {
+        None => Ok(term),
+        Some(e) => Err(e),
+    }
+}
+
+/// 構文エラーから回復しながら読み込む
+///
+/// 構文エラーのあった部分を `Term::Error` に置き換えた構文木と、全ての構文エラーを返す
+pub fn parse_with_recovery(s: &str) -> (Term, Vec<ParseError>) {
+    let mut tokens = Tokens::new(s);
+    let term = read_term(&mut tokens);
+    (term, tokens.errors)
+}
+
+/// 先読み可能なトークン列
+struct Tokens<'a> {
+    source: &'a str,
+    tokens: Vec<(Result<Token, ()>, Span)>,
+    pos: usize,
+    /// 回復済みの構文エラー
+    errors: Vec<ParseError>,
+    /// 読み込み中の関数本体 (`{ ... }`) の入れ子の深さ
+    block_depth: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str) -> Self {
+        let lexer = <Token as logos::Logos>::lexer(source);
+        Self {
+            source,
+            tokens: lexer
+                .spanned()
+                .map(|(token, span)| (token, Span::from(span)))
+                .collect(),
+            pos: 0,
+            errors: vec![],
+            block_depth: 0,
+        }
+    }
+
+    /// 次のトークンを読み進めずに返す (入力の終わりでは `None`)
+    fn peek(&self) -> Result<Option<&Token>, ParseError> {
+        self.peek_nth(0)
+    }
+
+    /// `n` 個先のトークンを読み進めずに返す
+    fn peek_nth(&self, n: usize) -> Result<Option<&Token>, ParseError> {
+        match self.tokens.get(self.pos + n) {
+            None => Ok(None),
+            Some((Ok(token), _)) => Ok(Some(token)),
+            Some((Err(()), span)) => Err(ParseError::InvalidToken {
+                text: self.source[span.range()].to_owned(),
+                span: *span,
+            }),
+        }
+    }
+
+    /// 次のトークンの位置 (入力の終わりでは末尾の位置)
+    fn peek_span(&self) -> Span {
+        match self.tokens.get(self.pos) {
+            None => Span::new(self.source.len(), self.source.len()),
+            Some((_, span)) => *span,
+        }
+    }
+
+    /// `start` から直前に読み進めたトークンまでの範囲
+    fn span_from(&self, start: Span) -> Span {
+        let end = match self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            None => start.start,
+            Some((_, span)) => span.end.max(start.start),
+        };
+        Span::new(start.start, end)
+    }
+
+    /// 次のトークンを読み進める (入力の終わりでは `expected` を期待していたとしてエラー)
+    fn next(&mut self, expected: &[&'static str]) -> Result<(Token, Span), ParseError> {
+        match self.tokens.get(self.pos) {
+            None => Err(ParseError::UnexpectedEof {
+                expected: expected.to_vec(),
+                span: self.peek_span(),
+            }),
+            Some((token, span)) => {
+                let span = *span;
+                self.pos += 1;
+                match token {
+                    Ok(token) => Ok((token.clone(), span)),
+                    Err(()) => Err(ParseError::InvalidToken {
+                        text: self.source[span.range()].to_owned(),
+                        span,
+                    }),
+                }
+            }
+        }
+    }
+
+    /// 次のトークンが `token` であることを確かめて読み進める
+    fn expect(&mut self, token: Token, name: &'static str) -> Result<Span, ParseError> {
+        let (found, span) = self.next(&[name])?;
+        if found == token {
+            Ok(span)
+        } else {
+            Err(ParseError::UnexpectedToken {
+                found,
+                expected: vec![name],
+                span,
+            })
+        }
+    }
+
+    /// 次のトークンが `expected` のいずれでもなかったことを表すエラー
+    fn unexpected(&mut self, expected: &[&'static str]) -> ParseError {
+        match self.next(expected) {
+            Ok((found, span)) => ParseError::UnexpectedToken {
+                found,
+                expected: expected.to_vec(),
+                span,
+            },
+            Err(e) => e,
+        }
+    }
+
+    /// エラーを記録し、エラーの原因となったトークンの手前まで戻る
+    fn recover(&mut self, e: ParseError) {
+        let start = e.span().start;
+        let pos = self
+            .tokens
+            .iter()
+            .position(|(_, span)| span.start >= start)
+            .unwrap_or(self.tokens.len());
+        self.pos = self.pos.min(pos);
+        self.errors.push(e);
+    }
+
+    /// `;` の直後まで読み飛ばす (括弧の中の `;` は文の区切りとみなさない)
+    ///
+    /// 関数本体の中では、本体を閉じる `}` に達した場合もその手前で止まる
+    fn skip_statement(&mut self) {
+        let mut depth = 0_usize;
+        while let Some((token, _)) = self.tokens.get(self.pos) {
+            if matches!(token, Ok(Token::BraceR)) && depth == 0 && self.block_depth > 0 {
+                break;
+            }
+            self.pos += 1;
             match token {
-                Token::False
-                | Token::True
-                | Token::Integer(_)
-                | Token::Ident(_)
-                | Token::ParenL => {
-                    let t = read_ternary(iter);
-                    match iter.peek() {
-                        None => return t,
-                        Some(token) => {
-                            let token = token.as_ref().unwrap();
-                            match token {
-                                Token::Semicolon => {
-                                    assert!(matches!(iter.next(), Some(Ok(Token::Semicolon))));
-                                    match iter.peek() {
-                                        None => return t,
-                                        Some(_) => {
-                                            let rest = read_term(iter);
-                                            Term::Seq {
-                                                body: Box::new(t),
-                                                rest: Box::new(rest),
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => unreachable!(),
-                            }
-                        }
+                Ok(Token::ParenL | Token::BraceL) => depth += 1,
+                Ok(Token::ParenR | Token::BraceR) => depth = depth.saturating_sub(1),
+                Ok(Token::Semicolon) if depth == 0 => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// 関数宣言の終わり (本体を閉じる `}` の直後) まで読み飛ばす
+    ///
+    /// 本体の `{` より前に文の区切りの `;` があれば、その直後で止まる
+    fn skip_func_decl(&mut self) {
+        let mut depth = 0_usize;
+        while let Some((token, _)) = self.tokens.get(self.pos) {
+            if matches!(token, Ok(Token::BraceR)) && depth == 0 && self.block_depth > 0 {
+                break;
+            }
+            self.pos += 1;
+            match token {
+                Ok(Token::ParenL | Token::BraceL) => depth += 1,
+                Ok(Token::ParenR) => depth = depth.saturating_sub(1),
+                Ok(Token::BraceR) => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        break;
                     }
                 }
-                Token::Const => {
-                    assert!(matches!(iter.next(), Some(Ok(Token::Const))));
-                    let name = match iter.next() {
-                        Some(Ok(Token::Ident(name))) => name,
-                        _ => unreachable!(),
-                    };
-                    assert!(matches!(iter.next(), Some(Ok(Token::Equals))));
-                    let init = read_ternary(iter);
-                    assert!(matches!(iter.next(), Some(Ok(Token::Semicolon))));
-                    let rest = read_term(iter);
-                    Term::Const {
-                        name,
-                        init: Box::new(init),
-                        rest: Box::new(rest),
-                    }
+                Ok(Token::Semicolon) if depth == 0 => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// 関数本体を閉じる `}` の手前まで読み飛ばす
+    fn skip_block(&mut self) {
+        let mut depth = 0_usize;
+        while let Some((token, _)) = self.tokens.get(self.pos) {
+            match token {
+                Ok(Token::BraceR) if depth == 0 => break,
+                Ok(Token::ParenL | Token::BraceL) => depth += 1,
+                Ok(Token::ParenR | Token::BraceR) => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// 対応する `)` の直後まで読み飛ばす
+    ///
+    /// `)` が見つからないまま文の区切りの `;` や `}` か入力の終わりに達した場合は、そこで止まって `false` を返す
+    fn skip_paren(&mut self) -> bool {
+        let mut depth = 0_usize;
+        while let Some((token, _)) = self.tokens.get(self.pos) {
+            match token {
+                Ok(Token::Semicolon | Token::BraceR) if depth == 0 => return false,
+                Ok(Token::ParenR) if depth == 0 => {
+                    self.pos += 1;
+                    return true;
                 }
-                Token::Plus
-                | Token::Quest
-                | Token::Colon
-                | Token::Semicolon
-                | Token::Equals
-                | Token::Comma
-                | Token::ParenR
-                | Token::Arrow => {
-                    unreachable!()
+                Ok(Token::ParenL | Token::BraceL) => depth += 1,
+                Ok(Token::ParenR | Token::BraceR) => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        false
+    }
+
+    /// `(` を読み込んだ直後に呼び出し、それが無名関数の引数の並びの始まりかどうかを判定する
+    ///
+    /// `()` や `(x:` で始まる場合と、対応する `)` の直後が `=>` か戻り値の型注釈と `=>` の場合に無名関数とみなす
+    fn is_func_start(&mut self) -> Result<bool, ParseError> {
+        if matches!(
+            (self.peek()?, self.peek_nth(1)?),
+            (Some(Token::ParenR), _) | (Some(Token::Ident(_)), Some(Token::Colon))
+        ) {
+            return Ok(true);
+        }
+        let mut depth = 0_usize;
+        let mut close = None;
+        for (i, (token, _)) in self.tokens.iter().enumerate().skip(self.pos) {
+            match token {
+                Ok(Token::ParenL) => depth += 1,
+                Ok(Token::ParenR) if depth == 0 => {
+                    close = Some(i);
+                    break;
                 }
+                Ok(Token::ParenR) => depth -= 1,
+                Ok(Token::Semicolon) if depth == 0 => break,
+                _ => {}
             }
         }
+        let Some(close) = close else {
+            return Ok(false);
+        };
+        let pos = self.pos;
+        self.pos = close + 1;
+        let is_func = match self.peek() {
+            Ok(Some(Token::Arrow)) => true,
+            // `c ? (x) : y` の `:` と区別するため、型注釈を読み込めて `=>` が続くか確かめる
+            Ok(Some(Token::Colon)) => {
+                self.pos += 1;
+                read_type(self).is_ok() && matches!(self.peek(), Ok(Some(Token::Arrow)))
+            }
+            _ => false,
+        };
+        self.pos = pos;
+        Ok(is_func)
+    }
+
+    /// 型エイリアスの宣言の始まり (`type` と識別子と `=`) であるか
+    ///
+    /// `type` は文脈依存のキーワードであり、それ以外の場所では変数名などの識別子として読み込む
+    fn is_type_decl_start(&self) -> bool {
+        matches!(
+            (self.peek(), self.peek_nth(1), self.peek_nth(2)),
+            (
+                Ok(Some(Token::Ident(keyword))),
+                Ok(Some(Token::Ident(_))),
+                Ok(Some(Token::Equals))
+            ) if keyword == "type"
+        )
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// 関数本体を閉じる `}` に達したか
+    fn is_block_end(&self) -> bool {
+        self.block_depth > 0 && matches!(self.peek(), Ok(Some(Token::BraceR)))
     }
 }
 
-fn read_primary_expr(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let token = iter.next().unwrap().unwrap();
-    match token {
-        Token::False => Term::False,
-        Token::True => Term::True,
-        Token::Integer(i) => Term::Integer(i),
-        Token::Ident(name) => {
-            if matches!(iter.peek(), Some(Ok(Token::ParenL))) {
-                assert!(matches!(iter.next(), Some(Ok(Token::ParenL))));
-                assert!(matches!(iter.next(), Some(Ok(Token::ParenR))));
-                let args = vec![];
-                // TODO
-                Term::Call {
-                    func: Box::new(Term::Var { name }),
-                    args,
-                }
-            } else {
-                Term::Var { name }
+// func         = "paren_l" "paren_r" ret_type "arrow" ternary | "paren_l" param_list "paren_r" ret_type "arrow" ternary
+// ret_type     = "" | "colon" type
+// param        = "ident" | "ident" "colon" type
+// param_list   = param | param "comma" param_list
+// typed_param_list = "ident" "colon" type | "ident" "colon" type "comma" typed_param_list
+// arg_list     = ternary | ternary "comma" arg_list
+// type_param_list = "ident" | "ident" "comma" type_param_list
+// generic_func = "lt" type_param_list "gt" func
+// paren        = "paren_l" ternary "paren_r"
+// primary_expr = "false" | "true" | "number" | "string" | "ident" | func | generic_func | paren | object
+// object       = "brace_l" "brace_r" | "brace_l" prop_list "brace_r"
+// prop         = "ident" "colon" ternary
+// prop_list    = prop | prop "comma" | prop "comma" prop_list
+// type_list    = type | type "comma" type_list
+// call         = primary_expr | call "paren_l" "paren_r" | call "paren_l" arg_list "paren_r" | call "dot" "ident" | call "lt" type_list "gt"
+// unary        = call | "typeof" unary
+// binary       = unary | unary "plus" binary
+// equality     = binary | equality "strict_equals" binary
+// ternary      = equality | equality "question" ternary "colon" ternary
+// const        = "const" "ident" "equals" ternary "semicolon" term
+// seq          = ternary "semicolon" term
+// term         = const | func_decls | type_decls | seq | ternary "semicolon"
+// func_decl    = "function" "ident" "paren_l" param_list "paren_r" "colon" type "brace_l" block "brace_r" | func_decl "semicolon"
+// func_decls   = func_decl decls_rest | func_decl func_decls
+// decls_rest   = "" | term (空であればプログラムの値は undefined)
+// block        = term (最後の文は return でなければならない)
+// return       = "return" ternary | "return" ternary "semicolon"
+// type_decl    = "type" "ident" "equals" type "semicolon" (`type` は識別子として読み込む文脈依存のキーワード)
+// type_decls   = type_decl decls_rest | type_decl type_decls
+// type         = primary_type | type "pipe" primary_type
+// primary_type = "number" | "boolean" | "string" | "undefined" | "ident" | "paren_l" type "paren_r" | func_type | generic_func_type | object_type
+// object_type  = "brace_l" "brace_r" | "brace_l" prop_type_list "brace_r"
+// prop_type    = "ident" "colon" type
+// prop_type_list = prop_type | prop_type ("semicolon" | "comma") | prop_type ("semicolon" | "comma") prop_type_list
+// func_type    = "paren_l" "paren_r" "arrow" type | "paren_l" typed_param_list "paren_r" "arrow" type
+// generic_func_type = "lt" type_param_list "gt" func_type
+
+/// 式の先頭に来ることのできるトークン
+const PRIMARY_EXPR_EXPECTED: &[&str] = &[
+    "`false`",
+    "`true`",
+    "number",
+    "string",
+    "identifier",
+    "`typeof`",
+    "`(`",
+    "`{`",
+    "`<`",
+];
+
+/// 型の先頭に来ることのできるトークン
+const TYPE_EXPECTED: &[&str] = &[
+    "`number`",
+    "`boolean`",
+    "`string`",
+    "identifier",
+    "`(`",
+    "`{`",
+    "`<`",
+];
+
+/// 関数呼び出しの `(` の直後に来ることのできるトークン
+const ARGS_EXPECTED: &[&str] = &[
+    "`false`",
+    "`true`",
+    "number",
+    "string",
+    "identifier",
+    "`typeof`",
+    "`(`",
+    "`{`",
+    "`<`",
+    "`)`",
+];
+
+/// 文の先頭に来ることのできるトークン
+const TERM_EXPECTED: &[&str] = &[
+    "`false`",
+    "`true`",
+    "number",
+    "string",
+    "identifier",
+    "`typeof`",
+    "`(`",
+    "`<`",
+    "`const`",
+    "`function`",
+];
+
+/// 文の並びを読み込む
+///
+/// 文の途中で構文エラーがあった場合は次の `;` まで読み飛ばし、その文を `Term::Error` として読み進める
+fn read_term(tokens: &mut Tokens<'_>) -> Term {
+    match tokens.peek() {
+        Ok(Some(Token::Const)) => return read_const(tokens),
+        Ok(Some(Token::Function)) => return read_func_decls(tokens),
+        _ if tokens.is_type_decl_start() => return read_type_decls(tokens),
+        _ => {}
+    }
+    let start = tokens.peek_span();
+    let is_return = tokens.block_depth > 0 && matches!(tokens.peek(), Ok(Some(Token::Return)));
+    if is_return {
+        tokens.pos += 1;
+    }
+    let body = match read_expr_statement(tokens) {
+        Ok(body) if !is_return && tokens.is_block_end() => {
+            // 関数本体は `return` 文で終わらなければならない
+            let e = tokens.unexpected(&["`return`"]);
+            tokens.recover(e);
+            body
+        }
+        Ok(body) => body,
+        Err(e) => {
+            tokens.recover(e);
+            tokens.skip_statement();
+            Term::Error {
+                span: tokens.span_from(start),
             }
         }
-        Token::ParenL => {
-            let mut params = vec![];
-            loop {
-                match iter.next().unwrap().unwrap() {
-                    Token::Ident(name) => {
-                        assert!(matches!(iter.next(), Some(Ok(Token::Colon))));
-                        match iter.next().unwrap().unwrap() {
-                            Token::Ident(typ) => {
-                                let typ = if typ == "number" {
-                                    Type::Integer
-                                } else if typ == "boolean" {
-                                    Type::Boolean
-                                } else {
-                                    todo!("function type: {}", typ);
-                                };
-                                params.push(Param { name, typ });
-                                match iter.next() {
-                                    Some(token) => match token.unwrap() {
-                                        Token::Comma => {
-                                            continue;
-                                        }
-                                        Token::ParenR => {
-                                            break;
-                                        }
-                                        _ => unreachable!(),
-                                    },
-                                    None => unreachable!(),
-                                }
-                            }
-                            _ => unreachable!(),
-                        }
-                    }
-                    Token::ParenR => {
-                        break;
-                    }
-                    Token::False
-                    | Token::True
-                    | Token::Integer(_)
-                    | Token::Plus
-                    | Token::Quest
-                    | Token::Colon
-                    | Token::Semicolon
-                    | Token::Const
-                    | Token::Equals
-                    | Token::ParenL
-                    | Token::Comma
-                    | Token::Arrow => unreachable!(),
+    };
+    if is_return && !tokens.is_eof() && !tokens.is_block_end() {
+        // `return` 文は関数本体の最後の文でなければならない
+        let e = tokens.unexpected(&["`}`"]);
+        tokens.recover(e);
+        tokens.skip_block();
+    }
+    read_rest(tokens, body)
+}
+
+/// 後続の文があれば `body` に続けて逐次実行する
+fn read_rest(tokens: &mut Tokens<'_>, body: Term) -> Term {
+    if tokens.is_eof() || tokens.is_block_end() {
+        return body;
+    }
+    let rest = read_term(tokens);
+    let span = body.span().to(rest.span());
+    Term::Seq {
+        body: Box::new(body),
+        rest: Box::new(rest),
+        span,
+    }
+}
+
+fn read_expr_statement(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    match tokens.peek()? {
+        Some(
+            Token::False
+            | Token::True
+            | Token::Number(_)
+            | Token::String(_)
+            | Token::Ident(_)
+            | Token::TypeOf
+            | Token::ParenL
+            | Token::Lt,
+        ) => {}
+        None
+        | Some(
+            Token::Plus
+            | Token::Quest
+            | Token::Colon
+            | Token::Semicolon
+            | Token::Const
+            | Token::Equals
+            | Token::Comma
+            | Token::ParenR
+            | Token::Arrow
+            | Token::Function
+            | Token::Return
+            | Token::BraceL
+            | Token::BraceR
+            | Token::Dot
+            | Token::StrictEquals
+            | Token::Pipe
+            | Token::Gt,
+        ) => return Err(tokens.unexpected(TERM_EXPECTED)),
+    }
+    let t = read_ternary(tokens)?;
+    match tokens.peek()? {
+        None => Ok(t),
+        Some(Token::Semicolon) => {
+            tokens.expect(Token::Semicolon, "`;`")?;
+            Ok(t)
+        }
+        // 関数本体の最後の文は `;` を省略できる
+        Some(Token::BraceR) if tokens.block_depth > 0 => Ok(t),
+        Some(_) if tokens.block_depth > 0 => {
+            Err(tokens.unexpected(&["`+`", "`===`", "`?`", "`;`", "`}`"]))
+        }
+        Some(_) => Err(tokens.unexpected(&["`+`", "`===`", "`?`", "`;`", "end of input"])),
+    }
+}
+
+fn read_const(tokens: &mut Tokens<'_>) -> Term {
+    let start = tokens.peek_span();
+    let name = match read_const_name(tokens) {
+        Ok(name) => name,
+        Err(e) => {
+            tokens.recover(e);
+            tokens.skip_statement();
+            let body = Term::Error {
+                span: tokens.span_from(start),
+            };
+            return read_rest(tokens, body);
+        }
+    };
+    let init_start = tokens.peek_span();
+    let init = match read_ternary(tokens).and_then(|init| {
+        tokens.expect(Token::Semicolon, "`;`")?;
+        Ok(init)
+    }) {
+        Ok(init) => init,
+        Err(e) => {
+            tokens.recover(e);
+            tokens.skip_statement();
+            Term::Error {
+                span: tokens.span_from(init_start),
+            }
+        }
+    };
+    let rest = read_term(tokens);
+    let span = start.to(rest.span());
+    Term::Const {
+        name,
+        init: Box::new(init),
+        rest: Box::new(rest),
+        span,
+    }
+}
+
+/// `const x =` までを読み込む
+fn read_const_name(tokens: &mut Tokens<'_>) -> Result<String, ParseError> {
+    tokens.expect(Token::Const, "`const`")?;
+    let (name, _) = read_ident(tokens)?;
+    tokens.expect(Token::Equals, "`=`")?;
+    Ok(name)
+}
+
+fn read_ident(tokens: &mut Tokens<'_>) -> Result<(String, Span), ParseError> {
+    match tokens.next(&["identifier"])? {
+        (Token::Ident(name), span) => Ok((name, span)),
+        (found, span) => Err(ParseError::UnexpectedToken {
+            found,
+            expected: vec!["identifier"],
+            span,
+        }),
+    }
+}
+
+/// 連続する関数宣言と、それに続く文の並びを読み込む
+///
+/// 関数宣言の途中で構文エラーがあった場合はその宣言を読み飛ばし、`Term::Error` として読み進める
+fn read_func_decls(tokens: &mut Tokens<'_>) -> Term {
+    let start = tokens.peek_span();
+    let mut funcs = vec![];
+    let rest = loop {
+        if !matches!(tokens.peek(), Ok(Some(Token::Function))) {
+            break read_decls_rest(tokens);
+        }
+        let decl_start = tokens.peek_span();
+        match read_func_decl(tokens) {
+            Ok(func) => {
+                funcs.push(func);
+                // 本体の後の `;` は空の文として読み飛ばす
+                if matches!(tokens.peek(), Ok(Some(Token::Semicolon))) {
+                    tokens.pos += 1;
                 }
             }
-            assert!(matches!(iter.next(), Some(Ok(Token::Arrow))));
-            let body = read_ternary(iter);
-            Term::Func {
-                params,
-                body: Box::new(body),
+            Err(e) => {
+                tokens.recover(e);
+                tokens.skip_func_decl();
+                let body = Term::Error {
+                    span: tokens.span_from(decl_start),
+                };
+                break read_rest(tokens, body);
             }
         }
+    };
+    if funcs.is_empty() {
+        return rest;
+    }
+    let span = start.to(rest.span());
+    Term::RecFunc {
+        funcs,
+        rest: Box::new(rest),
+        span,
+    }
+}
+
+/// 宣言に続く文の並びを読み込む
+///
+/// 宣言でプログラムが終わる場合は、その値を `undefined` とする
+fn read_decls_rest(tokens: &mut Tokens<'_>) -> Term {
+    if tokens.is_eof() {
+        return Term::Var {
+            name: "undefined".to_owned(),
+            span: tokens.peek_span(),
+        };
+    }
+    read_term(tokens)
+}
+
+/// 連続する型エイリアスの宣言と、それに続く文の並びを読み込む
+///
+/// 宣言の途中で構文エラーがあった場合は次の `;` まで読み飛ばし、`Term::Error` として読み進める
+fn read_type_decls(tokens: &mut Tokens<'_>) -> Term {
+    let start = tokens.peek_span();
+    let mut decls = vec![];
+    let rest = loop {
+        if !tokens.is_type_decl_start() {
+            break read_decls_rest(tokens);
+        }
+        let decl_start = tokens.peek_span();
+        let decl_pos = tokens.pos;
+        match read_type_decl(tokens) {
+            Ok(decl) => decls.push(decl),
+            Err(e) => {
+                tokens.recover(e);
+                // オブジェクト型の中の `;` で止まらないよう、宣言の先頭から読み飛ばす
+                tokens.pos = decl_pos;
+                tokens.skip_statement();
+                let body = Term::Error {
+                    span: tokens.span_from(decl_start),
+                };
+                break read_rest(tokens, body);
+            }
+        }
+    };
+    if decls.is_empty() {
+        return rest;
+    }
+    let span = start.to(rest.span());
+    Term::TypeDecls {
+        decls,
+        rest: Box::new(rest),
+        span,
+    }
+}
+
+fn read_type_decl(tokens: &mut Tokens<'_>) -> Result<TypeDecl, ParseError> {
+    let (_, start) = read_ident(tokens)?;
+    let (name, _) = read_ident(tokens)?;
+    tokens.expect(Token::Equals, "`=`")?;
+    let typ = read_type(tokens)?;
+    let end = tokens.expect(Token::Semicolon, "`;`")?;
+    Ok(TypeDecl {
+        name,
+        typ,
+        span: start.to(end),
+    })
+}
+
+fn read_func_decl(tokens: &mut Tokens<'_>) -> Result<FuncDecl, ParseError> {
+    let start = tokens.expect(Token::Function, "`function`")?;
+    let (name, _) = read_ident(tokens)?;
+    tokens.expect(Token::ParenL, "`(`")?;
+    let params = read_params(tokens, false)?;
+    tokens.expect(Token::Colon, "`:`")?;
+    let ret_type = read_type(tokens)?;
+    tokens.expect(Token::BraceL, "`{`")?;
+    tokens.block_depth += 1;
+    let body = read_term(tokens);
+    tokens.block_depth -= 1;
+    let end = tokens.expect(Token::BraceR, "`}`")?;
+    Ok(FuncDecl {
+        name,
+        params,
+        ret_type,
+        body,
+        span: start.to(end),
+    })
+}
+
+fn read_primary_expr(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let (token, span) = tokens.next(PRIMARY_EXPR_EXPECTED)?;
+    match token {
+        Token::False => Ok(Term::False(span)),
+        Token::True => Ok(Term::True(span)),
+        Token::Number(i) => Ok(Term::Number(i, span)),
+        Token::String(s) => Ok(Term::String(s, span)),
+        Token::Ident(name) => Ok(Term::Var { name, span }),
+        Token::BraceL => read_object(tokens, span),
+        Token::ParenL if !tokens.is_func_start()? => {
+            let term = read_ternary(tokens)?;
+            match tokens.peek()? {
+                Some(Token::ParenR) => {
+                    tokens.expect(Token::ParenR, "`)`")?;
+                    Ok(term)
+                }
+                _ => Err(tokens.unexpected(&["`+`", "`===`", "`?`", "`)`"])),
+            }
+        }
+        Token::ParenL => read_func(tokens, span, vec![]),
+        Token::Lt => {
+            let type_params = read_type_params(tokens)?;
+            tokens.expect(Token::ParenL, "`(`")?;
+            read_func(tokens, span, type_params)
+        }
         Token::Plus
         | Token::Quest
         | Token::Colon
@@ -175,136 +751,1105 @@ fn read_primary_expr(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) ->
         | Token::Equals
         | Token::Comma
         | Token::ParenR
-        | Token::Arrow => unreachable!(),
+        | Token::Arrow
+        | Token::Function
+        | Token::Return
+        | Token::BraceR
+        | Token::Dot
+        | Token::TypeOf
+        | Token::StrictEquals
+        | Token::Pipe
+        | Token::Gt => Err(ParseError::UnexpectedToken {
+            found: token,
+            expected: PRIMARY_EXPR_EXPECTED.to_vec(),
+            span,
+        }),
     }
 }
 
-fn read_binary(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let unary = read_primary_expr(iter);
-    match iter.peek() {
-        None => unary,
-        Some(token) => {
-            let token = token.as_ref().unwrap();
-            match token {
-                Token::False
-                | Token::True
-                | Token::Integer(_)
-                | Token::Ident(_)
-                | Token::Const
-                | Token::Equals
-                | Token::ParenL
-                | Token::Comma
-                | Token::ParenR
-                | Token::Arrow => unreachable!(),
-                Token::Quest | Token::Colon | Token::Semicolon => unary,
-                Token::Plus => {
-                    assert!(matches!(iter.next(), Some(Ok(Token::Plus))));
-                    let left = unary;
-                    let right = read_binary(iter);
-                    Term::Add {
-                        left: Box::new(left),
-                        right: Box::new(right),
+/// 無名関数の引数の並びの `(` に続く部分を読み込む (`start` は関数の先頭の位置)
+///
+/// 引数の途中で構文エラーがあった場合は対応する `)` まで読み飛ばし、その関数を `Term::Error` とする
+fn read_func(
+    tokens: &mut Tokens<'_>,
+    start: Span,
+    type_params: Vec<TypeParam>,
+) -> Result<Term, ParseError> {
+    let params = match read_params(tokens, false) {
+        Ok(params) => Some(params),
+        Err(e) => {
+            tokens.recover(e);
+            if !tokens.skip_paren() {
+                return Ok(Term::Error {
+                    span: tokens.span_from(start),
+                });
+            }
+            None
+        }
+    };
+    let ret_type = match tokens.peek()? {
+        Some(Token::Colon) => {
+            tokens.expect(Token::Colon, "`:`")?;
+            Some(read_type(tokens)?)
+        }
+        Some(Token::Arrow) => None,
+        _ => return Err(tokens.unexpected(&["`:`", "`=>`"])),
+    };
+    tokens.expect(Token::Arrow, "`=>`")?;
+    let body = read_ternary(tokens)?;
+    let span = start.to(body.span());
+    match params {
+        Some(params) => Ok(Term::Func {
+            type_params,
+            params,
+            ret_type,
+            body: Box::new(body),
+            span,
+        }),
+        None => Ok(Term::Error { span }),
+    }
+}
+
+/// `<` に続く型引数の宣言の並びを `>` まで読み込む
+fn read_type_params(tokens: &mut Tokens<'_>) -> Result<Vec<TypeParam>, ParseError> {
+    let mut type_params = vec![];
+    loop {
+        let (name, span) = read_ident(tokens)?;
+        type_params.push(TypeParam { name, span });
+        match tokens.next(&["`,`", "`>`"])? {
+            (Token::Comma, _) => continue,
+            (Token::Gt, _) => break Ok(type_params),
+            (found, span) => {
+                break Err(ParseError::UnexpectedToken {
+                    found,
+                    expected: vec!["`,`", "`>`"],
+                    span,
+                });
+            }
+        }
+    }
+}
+
+/// `<` に続く型引数の指定の並びを `>` まで読み込み、型引数と `>` の位置を返す
+fn read_type_args(tokens: &mut Tokens<'_>) -> Result<(Vec<(Type, Span)>, Span), ParseError> {
+    let mut type_args = vec![];
+    loop {
+        type_args.push(read_type(tokens)?);
+        match tokens.next(&["`|`", "`,`", "`>`"])? {
+            (Token::Comma, _) => continue,
+            (Token::Gt, end) => break Ok((type_args, end)),
+            (found, span) => {
+                break Err(ParseError::UnexpectedToken {
+                    found,
+                    expected: vec!["`|`", "`,`", "`>`"],
+                    span,
+                });
+            }
+        }
+    }
+}
+
+/// `{` に続くオブジェクトリテラルのプロパティの並びを `}` まで読み込む
+fn read_object(tokens: &mut Tokens<'_>, start: Span) -> Result<Term, ParseError> {
+    const PROP_END: &[&str] = &["`+`", "`===`", "`?`", "`,`", "`}`"];
+    let mut props = vec![];
+    loop {
+        let name = match tokens.next(&["identifier", "`}`"])? {
+            (Token::Ident(name), _) => name,
+            (Token::BraceR, end) => {
+                break Ok(Term::Object {
+                    props,
+                    span: start.to(end),
+                });
+            }
+            (found, span) => {
+                break Err(ParseError::UnexpectedToken {
+                    found,
+                    expected: vec!["identifier", "`}`"],
+                    span,
+                });
+            }
+        };
+        tokens.expect(Token::Colon, "`:`")?;
+        props.push((name, read_ternary(tokens)?));
+        match tokens.next(PROP_END)? {
+            (Token::Comma, _) => continue,
+            (Token::BraceR, end) => {
+                break Ok(Term::Object {
+                    props,
+                    span: start.to(end),
+                });
+            }
+            (found, span) => {
+                break Err(ParseError::UnexpectedToken {
+                    found,
+                    expected: PROP_END.to_vec(),
+                    span,
+                });
+            }
+        }
+    }
+}
+
+/// `(` に続く引数の並びを `)` まで読み込む
+///
+/// `typed` が真 (関数型の引数) の場合は型注釈を省略できない
+fn read_params(tokens: &mut Tokens<'_>, typed: bool) -> Result<Vec<Param>, ParseError> {
+    let mut params = vec![];
+    loop {
+        let (token, param_span) = tokens.next(&["identifier", "`)`"])?;
+        match token {
+            Token::Ident(name) => {
+                let (typ, span, expected): (_, _, &[_]) = match tokens.peek()? {
+                    Some(Token::Colon) => {
+                        tokens.expect(Token::Colon, "`:`")?;
+                        let (typ, typ_span) = read_type(tokens)?;
+                        (Some(typ), param_span.to(typ_span), &["`,`", "`)`"])
+                    }
+                    _ if typed => return Err(tokens.unexpected(&["`:`"])),
+                    _ => (None, param_span, &["`:`", "`,`", "`)`"]),
+                };
+                params.push(Param { name, typ, span });
+                match tokens.next(expected)? {
+                    (Token::Comma, _) => {
+                        continue;
+                    }
+                    (Token::ParenR, _) => {
+                        break;
+                    }
+                    (found, span) => {
+                        return Err(ParseError::UnexpectedToken {
+                            found,
+                            expected: expected.to_vec(),
+                            span,
+                        });
                     }
                 }
             }
+            Token::ParenR => {
+                break;
+            }
+            Token::False
+            | Token::True
+            | Token::Number(_)
+            | Token::Plus
+            | Token::Quest
+            | Token::Colon
+            | Token::Semicolon
+            | Token::Const
+            | Token::Equals
+            | Token::ParenL
+            | Token::Comma
+            | Token::Arrow
+            | Token::Function
+            | Token::Return
+            | Token::BraceL
+            | Token::BraceR
+            | Token::Dot
+            | Token::String(_)
+            | Token::TypeOf
+            | Token::StrictEquals
+            | Token::Pipe
+            | Token::Lt
+            | Token::Gt => {
+                return Err(ParseError::UnexpectedToken {
+                    found: token,
+                    expected: vec!["identifier", "`)`"],
+                    span: param_span,
+                });
+            }
         }
     }
+    Ok(params)
 }
 
-fn read_ternary(iter: &mut std::iter::Peekable<logos::Lexer<'_, Token>>) -> Term {
-    let binary = read_binary(iter);
-    match iter.peek() {
-        None => binary,
-        Some(token) => {
-            let token = token.as_ref().unwrap();
-            match token {
-                Token::False
-                | Token::True
-                | Token::Integer(_)
-                | Token::Plus
-                | Token::Ident(_)
-                | Token::Const
-                | Token::Equals
-                | Token::ParenL
-                | Token::Comma
-                | Token::ParenR
-                | Token::Arrow => unreachable!(),
-                Token::Quest => {
-                    assert!(matches!(iter.next(), Some(Ok(Token::Quest))));
-                    let cond = binary;
-                    let thn = read_ternary(iter);
-                    assert!(matches!(iter.next(), Some(Ok(Token::Colon))));
-                    let els = read_ternary(iter);
-                    Term::If {
-                        cond: Box::new(cond),
-                        thn: Box::new(thn),
-                        els: Box::new(els),
+/// 型注釈を読み込む (例: `number`, `(x: number) => boolean`, `{ a: number }`, `number | boolean`)
+fn read_type(tokens: &mut Tokens<'_>) -> Result<(Type, Span), ParseError> {
+    let (typ, start) = read_primary_type(tokens)?;
+    let mut types = vec![typ];
+    let mut end = start;
+    while let Some(Token::Pipe) = tokens.peek()? {
+        tokens.expect(Token::Pipe, "`|`")?;
+        let (typ, span) = read_primary_type(tokens)?;
+        types.push(typ);
+        end = span;
+    }
+    Ok((Type::union(types), start.to(end)))
+}
+
+/// `|` を含まない型注釈を読み込む (例: `number`, `((x: number) => number)`, `{ a: number }`, `<T>(x: T) => T`)
+fn read_primary_type(tokens: &mut Tokens<'_>) -> Result<(Type, Span), ParseError> {
+    match tokens.next(TYPE_EXPECTED)? {
+        (Token::Ident(typ), span) if typ == "number" => Ok((Type::Number, span)),
+        (Token::Ident(typ), span) if typ == "boolean" => Ok((Type::Boolean, span)),
+        (Token::Ident(typ), span) if typ == "string" => Ok((Type::String, span)),
+        (Token::Ident(typ), span) if typ == "undefined" => Ok((Type::Undefined, span)),
+        (Token::Ident(name), span) => Ok((Type::Ref(name), span)),
+        (Token::ParenL, start) => {
+            // `()` や `(x:` で始まれば関数型、それ以外は括弧で囲まれた型
+            let is_func_type = matches!(
+                (tokens.peek()?, tokens.peek_nth(1)?),
+                (Some(Token::ParenR), _) | (Some(Token::Ident(_)), Some(Token::Colon))
+            );
+            if is_func_type {
+                let (func, span) = read_func_type(tokens)?;
+                Ok((func, start.to(span)))
+            } else {
+                let (typ, _) = read_type(tokens)?;
+                let end = tokens.expect(Token::ParenR, "`)`")?;
+                Ok((typ, start.to(end)))
+            }
+        }
+        (Token::BraceL, start) => {
+            let mut props = vec![];
+            loop {
+                let name = match tokens.next(&["identifier", "`}`"])? {
+                    (Token::Ident(name), _) => name,
+                    (Token::BraceR, end) => break Ok((Type::Object { props }, start.to(end))),
+                    (found, span) => {
+                        break Err(ParseError::UnexpectedToken {
+                            found,
+                            expected: vec!["identifier", "`}`"],
+                            span,
+                        });
+                    }
+                };
+                tokens.expect(Token::Colon, "`:`")?;
+                let (typ, _) = read_type(tokens)?;
+                props.push(typ::Prop { name, typ });
+                match tokens.next(&["`;`", "`,`", "`}`"])? {
+                    (Token::Semicolon | Token::Comma, _) => continue,
+                    (Token::BraceR, end) => break Ok((Type::Object { props }, start.to(end))),
+                    (found, span) => {
+                        break Err(ParseError::UnexpectedToken {
+                            found,
+                            expected: vec!["`;`", "`,`", "`}`"],
+                            span,
+                        });
+                    }
+                }
+            }
+        }
+        (Token::Lt, start) => {
+            let type_params = read_type_params(tokens)?
+                .into_iter()
+                .map(|type_param| typ::TypeParam {
+                    name: type_param.name,
+                    id: 0,
+                })
+                .collect();
+            tokens.expect(Token::ParenL, "`(`")?;
+            let (func, span) = read_func_type(tokens)?;
+            Ok((
+                Type::Generic {
+                    type_params,
+                    func: Box::new(func),
+                },
+                start.to(span),
+            ))
+        }
+        (found, span) => Err(ParseError::UnexpectedToken {
+            found,
+            expected: TYPE_EXPECTED.to_vec(),
+            span,
+        }),
+    }
+}
+
+/// 関数型の `(` に続く部分を読み込み、関数型と戻り値の型の位置を返す
+fn read_func_type(tokens: &mut Tokens<'_>) -> Result<(Type, Span), ParseError> {
+    let params = read_params(tokens, true)?
+        .into_iter()
+        .map(|param| typ::Param {
+            name: param.name,
+            typ: param.typ.unwrap_or(Type::Unknown),
+        })
+        .collect();
+    tokens.expect(Token::Arrow, "`=>`")?;
+    let (ret_type, ret_span) = read_type(tokens)?;
+    Ok((
+        Type::Func {
+            params,
+            ret_type: Box::new(ret_type),
+        },
+        ret_span,
+    ))
+}
+
+/// 式に続く関数呼び出しとプロパティの参照、型引数の指定の並びを読み込む (例: `f(1)`, `f(1)(true, 2)`, `o.a.b`, `f<number>(1)`)
+///
+/// 引数の途中で構文エラーがあった場合は対応する `)` まで読み飛ばし、その呼び出しを `Term::Error` とする
+fn read_call(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let mut term = read_primary_expr(tokens)?;
+    loop {
+        let start = term.span();
+        term = match tokens.peek()? {
+            Some(Token::ParenL) => {
+                tokens.expect(Token::ParenL, "`(`")?;
+                match read_args(tokens) {
+                    Ok((args, paren_r)) => Term::Call {
+                        func: Box::new(term),
+                        args,
+                        span: start.to(paren_r),
+                    },
+                    Err(e) => {
+                        tokens.recover(e);
+                        let closed = tokens.skip_paren();
+                        let error = Term::Error {
+                            span: tokens.span_from(start),
+                        };
+                        if !closed {
+                            return Ok(error);
+                        }
+                        error
                     }
                 }
-                Token::Colon | Token::Semicolon => binary,
+            }
+            Some(Token::Dot) => {
+                tokens.expect(Token::Dot, "`.`")?;
+                let (name, name_span) = read_ident(tokens)?;
+                Term::Get {
+                    obj: Box::new(term),
+                    name,
+                    span: start.to(name_span),
+                }
+            }
+            Some(Token::Lt) => {
+                tokens.expect(Token::Lt, "`<`")?;
+                let (type_args, end) = read_type_args(tokens)?;
+                Term::Instantiate {
+                    func: Box::new(term),
+                    type_args,
+                    span: start.to(end),
+                }
+            }
+            _ => return Ok(term),
+        };
+    }
+}
+
+/// `(` に続く実引数の並びを `)` まで読み込み、実引数と `)` の位置を返す
+fn read_args(tokens: &mut Tokens<'_>) -> Result<(Vec<Term>, Span), ParseError> {
+    const ARG_END: &[&str] = &["`+`", "`===`", "`?`", "`,`", "`)`"];
+    let mut args = vec![];
+    match tokens.peek()? {
+        Some(Token::ParenR) => {
+            let paren_r = tokens.expect(Token::ParenR, "`)`")?;
+            return Ok((args, paren_r));
+        }
+        Some(
+            Token::False
+            | Token::True
+            | Token::Number(_)
+            | Token::String(_)
+            | Token::Ident(_)
+            | Token::TypeOf
+            | Token::ParenL
+            | Token::BraceL
+            | Token::Lt,
+        ) => {}
+        None
+        | Some(
+            Token::Plus
+            | Token::Quest
+            | Token::Colon
+            | Token::Semicolon
+            | Token::Const
+            | Token::Equals
+            | Token::Comma
+            | Token::Arrow
+            | Token::Function
+            | Token::Return
+            | Token::BraceR
+            | Token::Dot
+            | Token::StrictEquals
+            | Token::Pipe
+            | Token::Gt,
+        ) => return Err(tokens.unexpected(ARGS_EXPECTED)),
+    }
+    loop {
+        args.push(read_ternary(tokens)?);
+        match tokens.next(ARG_END)? {
+            (Token::Comma, _) => continue,
+            (Token::ParenR, paren_r) => return Ok((args, paren_r)),
+            (found, span) => {
+                return Err(ParseError::UnexpectedToken {
+                    found,
+                    expected: ARG_END.to_vec(),
+                    span,
+                });
             }
         }
     }
 }
 
+fn read_unary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    match tokens.peek()? {
+        Some(Token::TypeOf) => {
+            let start = tokens.expect(Token::TypeOf, "`typeof`")?;
+            let expr = read_unary(tokens)?;
+            let span = start.to(expr.span());
+            Ok(Term::TypeOf {
+                expr: Box::new(expr),
+                span,
+            })
+        }
+        _ => read_call(tokens),
+    }
+}
+
+fn read_binary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let unary = read_unary(tokens)?;
+    match tokens.peek()? {
+        Some(Token::Plus) => {
+            tokens.expect(Token::Plus, "`+`")?;
+            let left = unary;
+            let right = read_binary(tokens)?;
+            let span = left.span().to(right.span());
+            Ok(Term::Add {
+                left: Box::new(left),
+                right: Box::new(right),
+                span,
+            })
+        }
+        _ => Ok(unary),
+    }
+}
+
+/// `===` は左結合 (例: `a === b === c` は `(a === b) === c`)
+fn read_equality(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let mut left = read_binary(tokens)?;
+    while let Some(Token::StrictEquals) = tokens.peek()? {
+        tokens.expect(Token::StrictEquals, "`===`")?;
+        let right = read_binary(tokens)?;
+        let span = left.span().to(right.span());
+        left = Term::StrictEq {
+            left: Box::new(left),
+            right: Box::new(right),
+            span,
+        };
+    }
+    Ok(left)
+}
+
+fn read_ternary(tokens: &mut Tokens<'_>) -> Result<Term, ParseError> {
+    let equality = read_equality(tokens)?;
+    match tokens.peek()? {
+        Some(Token::Quest) => {
+            tokens.expect(Token::Quest, "`?`")?;
+            let cond = equality;
+            let thn = read_ternary(tokens)?;
+            tokens.expect(Token::Colon, "`:`")?;
+            let els = read_ternary(tokens)?;
+            let span = cond.span().to(els.span());
+            Ok(Term::If {
+                cond: Box::new(cond),
+                thn: Box::new(thn),
+                els: Box::new(els),
+                span,
+            })
+        }
+        _ => Ok(equality),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn test_parse(s: &str, expected: Term) {
-        let term = parse(s);
-        assert_eq!(term, expected);
+    use crate::tiny_ts::span::without_spans;
+
+    const S: Span = Span::new(0, 0);
+
+    fn test_parse(s: &str, expected: Term) {
+        let term = parse(s).unwrap();
+        assert_eq!(without_spans(term), expected);
+    }
+
+    #[test]
+    fn test_primary_expr() {
+        test_parse("true", Term::True(S));
+        test_parse("false", Term::False(S));
+        test_parse("0", Term::Number(0.0, S));
+        test_parse("256", Term::Number(256.0, S));
+        test_parse("1_000.25", Term::Number(1000.25, S));
+        test_parse("0xFF_ff", Term::Number(65535.0, S));
+        test_parse("0b1010", Term::Number(10.0, S));
+        test_parse(
+            "x",
+            Term::Var {
+                name: "x".to_owned(),
+                span: S,
+            },
+        );
+        test_parse(
+            "(x: number) => x",
+            Term::Func {
+                type_params: vec![],
+                params: vec![Param {
+                    name: "x".to_owned(),
+                    typ: Some(Type::Number),
+                    span: S,
+                }],
+                ret_type: None,
+                body: Box::new(Term::Var {
+                    name: "x".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+        test_parse(
+            "(y: number, z: boolean) => y",
+            Term::Func {
+                type_params: vec![],
+                params: vec![
+                    Param {
+                        name: "y".to_owned(),
+                        typ: Some(Type::Number),
+                        span: S,
+                    },
+                    Param {
+                        name: "z".to_owned(),
+                        typ: Some(Type::Boolean),
+                        span: S,
+                    },
+                ],
+                ret_type: None,
+                body: Box::new(Term::Var {
+                    name: "y".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+        test_parse(
+            "f()",
+            Term::Call {
+                func: Box::new(Term::Var {
+                    name: "f".to_owned(),
+                    span: S,
+                }),
+                args: vec![],
+                span: S,
+            },
+        );
+    }
+
+    #[test]
+    fn test_object() {
+        test_parse(
+            "({})",
+            Term::Object {
+                props: vec![],
+                span: S,
+            },
+        );
+        test_parse(
+            "({ a: 1, b: { c: true }, })",
+            Term::Object {
+                props: vec![
+                    ("a".to_owned(), Term::Number(1.0, S)),
+                    (
+                        "b".to_owned(),
+                        Term::Object {
+                            props: vec![("c".to_owned(), Term::True(S))],
+                            span: S,
+                        },
+                    ),
+                ],
+                span: S,
+            },
+        );
+        // プロパティ参照は呼び出しと同じく左結合
+        test_parse(
+            "o.a.b",
+            Term::Get {
+                obj: Box::new(Term::Get {
+                    obj: Box::new(Term::Var {
+                        name: "o".to_owned(),
+                        span: S,
+                    }),
+                    name: "a".to_owned(),
+                    span: S,
+                }),
+                name: "b".to_owned(),
+                span: S,
+            },
+        );
+        test_parse(
+            "f().a",
+            Term::Get {
+                obj: Box::new(Term::Call {
+                    func: Box::new(Term::Var {
+                        name: "f".to_owned(),
+                        span: S,
+                    }),
+                    args: vec![],
+                    span: S,
+                }),
+                name: "a".to_owned(),
+                span: S,
+            },
+        );
+
+        let Term::Const { init, .. } = parse("const x = { a: 1 }.a; x;").unwrap() else {
+            panic!("const expected");
+        };
+        let Term::Get { obj, span, .. } = *init else {
+            panic!("property access expected");
+        };
+        assert_eq!(obj.span().range(), 10..18);
+        assert_eq!(span.range(), 10..20);
+
+        // JavaScript と同じく、文の先頭の `{` はオブジェクトとして読まない
+        assert_eq!(
+            parse("{ a: 1 };"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::BraceL,
+                expected: TERM_EXPECTED.to_vec(),
+                span: Span::new(0, 1),
+            })
+        );
+        assert_eq!(
+            parse("({ a: 1 b: 2 })"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Ident("b".to_owned()),
+                expected: vec!["`+`", "`===`", "`?`", "`,`", "`}`"],
+                span: Span::new(8, 9),
+            })
+        );
+        assert_eq!(
+            parse("o.1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Number(1.0),
+                expected: vec!["identifier"],
+                span: Span::new(2, 3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_type() {
+        let number = || Box::new(Type::Number);
+        let param = |name: &str, typ: Type| typ::Param {
+            name: name.to_owned(),
+            typ,
+        };
+        let prop = |name: &str, typ: Type| typ::Prop {
+            name: name.to_owned(),
+            typ,
+        };
+        let param_type = |s: &str| match parse(s).unwrap() {
+            Term::Func { mut params, .. } => params.remove(0).typ.unwrap(),
+            t => panic!("function expected, but got {:?}", t),
+        };
+        assert_eq!(param_type("(f: (number)) => 1"), Type::Number);
+        assert_eq!(
+            param_type("(f: () => number) => 1"),
+            Type::Func {
+                params: vec![],
+                ret_type: number(),
+            }
+        );
+        assert_eq!(
+            param_type("(f: (x: number, y: boolean) => number) => 1"),
+            Type::Func {
+                params: vec![param("x", Type::Number), param("y", Type::Boolean)],
+                ret_type: number(),
+            }
+        );
+        // 関数型の `=>` は右結合
+        assert_eq!(
+            param_type("(f: (x: number) => (y: number) => number) => 1"),
+            Type::Func {
+                params: vec![param("x", Type::Number)],
+                ret_type: Box::new(Type::Func {
+                    params: vec![param("y", Type::Number)],
+                    ret_type: number(),
+                }),
+            }
+        );
+        assert_eq!(
+            param_type("(f: ((g: (x: number) => boolean) => number)) => 1"),
+            Type::Func {
+                params: vec![param(
+                    "g",
+                    Type::Func {
+                        params: vec![param("x", Type::Number)],
+                        ret_type: Box::new(Type::Boolean),
+                    }
+                )],
+                ret_type: number(),
+            }
+        );
+
+        let Term::Func { params, .. } =
+            parse("(f: (x: number) => number, y: boolean) => y").unwrap()
+        else {
+            panic!("function expected");
+        };
+        assert_eq!(params[0].span.range(), 1..25);
+        assert_eq!(params[1].span.range(), 27..37);
+
+        assert_eq!(param_type("(o: {}) => 1"), Type::Object { props: vec![] });
+        assert_eq!(
+            param_type("(o: { a: number; b: { c: boolean }, f: () => number }) => 1"),
+            Type::Object {
+                props: vec![
+                    prop("a", Type::Number),
+                    prop(
+                        "b",
+                        Type::Object {
+                            props: vec![prop("c", Type::Boolean)],
+                        }
+                    ),
+                    prop(
+                        "f",
+                        Type::Func {
+                            params: vec![],
+                            ret_type: number(),
+                        }
+                    ),
+                ],
+            }
+        );
+
+        assert_eq!(
+            param_type("(x: number | boolean | number) => 1"),
+            Type::Union(vec![Type::Number, Type::Boolean])
+        );
+        // `=>` の右側の `|` は戻り値の型に含まれる
+        assert_eq!(
+            param_type("(f: (() => number | string) | boolean) => 1"),
+            Type::Union(vec![
+                Type::Func {
+                    params: vec![],
+                    ret_type: Box::new(Type::Union(vec![Type::Number, Type::String])),
+                },
+                Type::Boolean,
+            ])
+        );
+        assert_eq!(
+            param_type("(l: { head: number; tail: List } | undefined) => 1"),
+            Type::Union(vec![
+                Type::Object {
+                    props: vec![
+                        prop("head", Type::Number),
+                        prop("tail", Type::Ref("List".to_owned())),
+                    ],
+                },
+                Type::Undefined,
+            ])
+        );
+        let Term::Func { params, .. } = parse("(x: number | { a: string }) => 1").unwrap() else {
+            panic!("function expected");
+        };
+        assert_eq!(params[0].span.range(), 1..26);
+
+        assert_eq!(
+            parse("(x: number | ) => 1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: TYPE_EXPECTED.to_vec(),
+                span: Span::new(13, 14),
+            })
+        );
+        assert_eq!(
+            parse("(f: (x: number) => ) => 1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: TYPE_EXPECTED.to_vec(),
+                span: Span::new(19, 20),
+            })
+        );
+        assert_eq!(
+            parse("(f: (number => number) => 1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Arrow,
+                expected: vec!["`)`"],
+                span: Span::new(12, 14),
+            })
+        );
+    }
+
+    #[test]
+    fn test_ret_type() {
+        test_parse(
+            "(): boolean => true",
+            Term::Func {
+                type_params: vec![],
+                params: vec![],
+                ret_type: Some((Type::Boolean, S)),
+                body: Box::new(Term::True(S)),
+                span: S,
+            },
+        );
+        let Term::Func { ret_type, .. } = parse("(x: number): () => number => () => x").unwrap()
+        else {
+            panic!("function expected");
+        };
+        let (typ, span) = ret_type.unwrap();
+        assert_eq!(typ.to_string(), "() => number");
+        assert_eq!(span.range(), 13..25);
+
+        // 型注釈のない引数と戻り値の型注釈を組み合わせられる
+        test_parse(
+            "(x): number => x",
+            Term::Func {
+                type_params: vec![],
+                params: vec![Param {
+                    name: "x".to_owned(),
+                    typ: None,
+                    span: S,
+                }],
+                ret_type: Some((Type::Number, S)),
+                body: Box::new(Term::Var {
+                    name: "x".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+        // `=>` が続かなければ条件演算子の `:` である
+        let Term::If { thn, .. } = parse("true ? (x) : y").unwrap() else {
+            panic!("conditional expected");
+        };
+        assert_eq!(
+            without_spans(*thn),
+            Term::Var {
+                name: "x".to_owned(),
+                span: S,
+            }
+        );
+
+        assert_eq!(
+            parse("(x: number) x"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Ident("x".to_owned()),
+                expected: vec!["`:`", "`=>`"],
+                span: Span::new(12, 13),
+            })
+        );
+        assert_eq!(
+            parse("(x: number): => x"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Arrow,
+                expected: TYPE_EXPECTED.to_vec(),
+                span: Span::new(13, 15),
+            })
+        );
+    }
+
+    #[test]
+    fn test_call() {
+        let var = |name: &str| {
+            Box::new(Term::Var {
+                name: name.to_owned(),
+                span: S,
+            })
+        };
+        test_parse(
+            "f(1)",
+            Term::Call {
+                func: var("f"),
+                args: vec![Term::Number(1.0, S)],
+                span: S,
+            },
+        );
+        test_parse(
+            "f(x + 1, g(y))",
+            Term::Call {
+                func: var("f"),
+                args: vec![
+                    Term::Add {
+                        left: var("x"),
+                        right: Box::new(Term::Number(1.0, S)),
+                        span: S,
+                    },
+                    Term::Call {
+                        func: var("g"),
+                        args: vec![*var("y")],
+                        span: S,
+                    },
+                ],
+                span: S,
+            },
+        );
+        test_parse(
+            "f(1)(true, 2)()",
+            Term::Call {
+                func: Box::new(Term::Call {
+                    func: Box::new(Term::Call {
+                        func: var("f"),
+                        args: vec![Term::Number(1.0, S)],
+                        span: S,
+                    }),
+                    args: vec![Term::True(S), Term::Number(2.0, S)],
+                    span: S,
+                }),
+                args: vec![],
+                span: S,
+            },
+        );
+        test_parse(
+            "1(2) + 3",
+            Term::Add {
+                left: Box::new(Term::Call {
+                    func: Box::new(Term::Number(1.0, S)),
+                    args: vec![Term::Number(2.0, S)],
+                    span: S,
+                }),
+                right: Box::new(Term::Number(3.0, S)),
+                span: S,
+            },
+        );
+
+        let term = parse("f(1)(true, 2)").unwrap();
+        let Term::Call { func, span, .. } = &term else {
+            panic!("call expected");
+        };
+        assert_eq!(span.range(), 0..13);
+        assert_eq!(func.span().range(), 0..4);
     }
 
     #[test]
-    fn test_primary_expr() {
-        test_parse("true", Term::True);
-        test_parse("false", Term::False);
-        test_parse("0", Term::Integer(0));
+    fn test_paren() {
+        let var = |name: &str| {
+            Box::new(Term::Var {
+                name: name.to_owned(),
+                span: S,
+            })
+        };
         test_parse(
-            "x",
-            Term::Var {
-                name: "x".to_owned(),
+            "(1 + 2) + 3",
+            Term::Add {
+                left: Box::new(Term::Add {
+                    left: Box::new(Term::Number(1.0, S)),
+                    right: Box::new(Term::Number(2.0, S)),
+                    span: S,
+                }),
+                right: Box::new(Term::Number(3.0, S)),
+                span: S,
             },
         );
         test_parse(
-            "(x: number) => x",
+            "true ? (1) : 2",
+            Term::If {
+                cond: Box::new(Term::True(S)),
+                thn: Box::new(Term::Number(1.0, S)),
+                els: Box::new(Term::Number(2.0, S)),
+                span: S,
+            },
+        );
+        test_parse(
+            "(f)(x)",
+            Term::Call {
+                func: var("f"),
+                args: vec![*var("x")],
+                span: S,
+            },
+        );
+        test_parse(
+            "((x: number) => x)(1)",
+            Term::Call {
+                func: Box::new(Term::Func {
+                    type_params: vec![],
+                    params: vec![Param {
+                        name: "x".to_owned(),
+                        typ: Some(Type::Number),
+                        span: S,
+                    }],
+                    ret_type: None,
+                    body: var("x"),
+                    span: S,
+                }),
+                args: vec![Term::Number(1.0, S)],
+                span: S,
+            },
+        );
+        test_parse(
+            "(f: (x: number) => number) => (f(1))",
             Term::Func {
+                type_params: vec![],
                 params: vec![Param {
-                    name: "x".to_owned(),
-                    typ: Type::Integer,
+                    name: "f".to_owned(),
+                    typ: Some(Type::Func {
+                        params: vec![typ::Param {
+                            name: "x".to_owned(),
+                            typ: Type::Number,
+                        }],
+                        ret_type: Box::new(Type::Number),
+                    }),
+                    span: S,
                 }],
-                body: Box::new(Term::Var {
-                    name: "x".to_owned(),
+                ret_type: None,
+                body: Box::new(Term::Call {
+                    func: var("f"),
+                    args: vec![Term::Number(1.0, S)],
+                    span: S,
                 }),
+                span: S,
             },
         );
+
+        // 注釈のない引数は `)` の後の `=>` で無名関数とわかる
         test_parse(
-            "(y: number, z: boolean) => y",
+            "(x, y: number) => x",
             Term::Func {
+                type_params: vec![],
                 params: vec![
                     Param {
-                        name: "y".to_owned(),
-                        typ: Type::Integer,
+                        name: "x".to_owned(),
+                        typ: None,
+                        span: S,
                     },
                     Param {
-                        name: "z".to_owned(),
-                        typ: Type::Boolean,
+                        name: "y".to_owned(),
+                        typ: Some(Type::Number),
+                        span: S,
                     },
                 ],
-                body: Box::new(Term::Var {
-                    name: "y".to_owned(),
-                }),
+                ret_type: None,
+                body: var("x"),
+                span: S,
             },
         );
-        test_parse(
-            "f()",
-            Term::Call {
-                func: Box::new(Term::Var {
-                    name: "f".to_owned(),
-                }),
-                args: vec![],
-            },
+        let Term::Func { params, .. } = parse("(x) => x").unwrap() else {
+            panic!("function expected");
+        };
+        assert_eq!(params[0].span.range(), 1..2);
+        assert_eq!(
+            parse("(x y) => x"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Ident("y".to_owned()),
+                expected: vec!["`:`", "`,`", "`)`"],
+                span: Span::new(3, 4),
+            })
+        );
+        // 関数型の引数には型注釈が必要
+        assert_eq!(
+            parse("(f: <T>(x) => T) => 1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: vec!["`:`"],
+                span: Span::new(9, 10),
+            })
+        );
+        assert_eq!(
+            parse("(1 + 2"),
+            Err(ParseError::UnexpectedEof {
+                expected: vec!["`+`", "`===`", "`?`", "`)`"],
+                span: Span::new(6, 6),
+            })
+        );
+        assert_eq!(
+            parse("(x y)"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Ident("y".to_owned()),
+                expected: vec!["`+`", "`===`", "`?`", "`)`"],
+                span: Span::new(3, 4),
+            })
         );
     }
 
@@ -313,20 +1858,97 @@ mod tests {
         test_parse(
             "1 + 2",
             Term::Add {
-                left: Box::new(Term::Integer(1)),
-                right: Box::new(Term::Integer(2)),
+                left: Box::new(Term::Number(1.0, S)),
+                right: Box::new(Term::Number(2.0, S)),
+                span: S,
             },
         );
         test_parse(
             "3 + 4 + 5",
             Term::Add {
-                left: Box::new(Term::Integer(3)),
+                left: Box::new(Term::Number(3.0, S)),
                 right: Box::new(Term::Add {
-                    left: Box::new(Term::Integer(4)),
-                    right: Box::new(Term::Integer(5)),
+                    left: Box::new(Term::Number(4.0, S)),
+                    right: Box::new(Term::Number(5.0, S)),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+    }
+
+    #[test]
+    fn test_equality() {
+        let var = |name: &str| {
+            Box::new(Term::Var {
+                name: name.to_owned(),
+                span: S,
+            })
+        };
+        test_parse("'abc'", Term::String("abc".to_owned(), S));
+        // `typeof` は `+` より、`+` は `===` より強く結合する
+        test_parse(
+            "typeof x + 1 === \"number\"",
+            Term::StrictEq {
+                left: Box::new(Term::Add {
+                    left: Box::new(Term::TypeOf {
+                        expr: var("x"),
+                        span: S,
+                    }),
+                    right: Box::new(Term::Number(1.0, S)),
+                    span: S,
+                }),
+                right: Box::new(Term::String("number".to_owned(), S)),
+                span: S,
+            },
+        );
+        test_parse(
+            "a === b === typeof typeof c",
+            Term::StrictEq {
+                left: Box::new(Term::StrictEq {
+                    left: var("a"),
+                    right: var("b"),
+                    span: S,
+                }),
+                right: Box::new(Term::TypeOf {
+                    expr: Box::new(Term::TypeOf {
+                        expr: var("c"),
+                        span: S,
+                    }),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+        test_parse(
+            "typeof x === 'boolean' ? x : 1",
+            Term::If {
+                cond: Box::new(Term::StrictEq {
+                    left: Box::new(Term::TypeOf {
+                        expr: var("x"),
+                        span: S,
+                    }),
+                    right: Box::new(Term::String("boolean".to_owned(), S)),
+                    span: S,
                 }),
+                thn: var("x"),
+                els: Box::new(Term::Number(1.0, S)),
+                span: S,
             },
         );
+
+        let Term::TypeOf { span, .. } = parse("typeof o.a").unwrap() else {
+            panic!("typeof expected");
+        };
+        assert_eq!(span.range(), 0..10);
+
+        assert_eq!(
+            parse("1 === "),
+            Err(ParseError::UnexpectedEof {
+                expected: PRIMARY_EXPR_EXPECTED.to_vec(),
+                span: Span::new(6, 6),
+            })
+        );
     }
 
     #[test]
@@ -334,33 +1956,38 @@ mod tests {
         test_parse(
             "true ? 6 : 7",
             Term::If {
-                cond: Box::new(Term::True),
-                thn: Box::new(Term::Integer(6)),
-                els: Box::new(Term::Integer(7)),
+                cond: Box::new(Term::True(S)),
+                thn: Box::new(Term::Number(6.0, S)),
+                els: Box::new(Term::Number(7.0, S)),
+                span: S,
             },
         );
         test_parse(
             "true ? true ? 8 : 9 : 10",
             Term::If {
-                cond: Box::new(Term::True),
+                cond: Box::new(Term::True(S)),
                 thn: Box::new(Term::If {
-                    cond: Box::new(Term::True),
-                    thn: Box::new(Term::Integer(8)),
-                    els: Box::new(Term::Integer(9)),
+                    cond: Box::new(Term::True(S)),
+                    thn: Box::new(Term::Number(8.0, S)),
+                    els: Box::new(Term::Number(9.0, S)),
+                    span: S,
                 }),
-                els: Box::new(Term::Integer(10)),
+                els: Box::new(Term::Number(10.0, S)),
+                span: S,
             },
         );
         test_parse(
             "true ? 11 : true ? 12 : 13",
             Term::If {
-                cond: Box::new(Term::True),
-                thn: Box::new(Term::Integer(11)),
+                cond: Box::new(Term::True(S)),
+                thn: Box::new(Term::Number(11.0, S)),
                 els: Box::new(Term::If {
-                    cond: Box::new(Term::True),
-                    thn: Box::new(Term::Integer(12)),
-                    els: Box::new(Term::Integer(13)),
+                    cond: Box::new(Term::True(S)),
+                    thn: Box::new(Term::Number(12.0, S)),
+                    els: Box::new(Term::Number(13.0, S)),
+                    span: S,
                 }),
+                span: S,
             },
         );
     }
@@ -371,16 +1998,18 @@ mod tests {
             "const y = 1; 2",
             Term::Const {
                 name: "y".to_owned(),
-                init: Box::new(Term::Integer(1)),
-                rest: Box::new(Term::Integer(2)),
+                init: Box::new(Term::Number(1.0, S)),
+                rest: Box::new(Term::Number(2.0, S)),
+                span: S,
             },
         );
         test_parse(
             "const z = 3; 4;",
             Term::Const {
                 name: "z".to_owned(),
-                init: Box::new(Term::Integer(3)),
-                rest: Box::new(Term::Integer(4)),
+                init: Box::new(Term::Number(3.0, S)),
+                rest: Box::new(Term::Number(4.0, S)),
+                span: S,
             },
         );
         test_parse(
@@ -388,12 +2017,15 @@ mod tests {
             Term::Const {
                 name: "aa".to_owned(),
                 init: Box::new(Term::Add {
-                    left: Box::new(Term::Integer(5)),
-                    right: Box::new(Term::Integer(6)),
+                    left: Box::new(Term::Number(5.0, S)),
+                    right: Box::new(Term::Number(6.0, S)),
+                    span: S,
                 }),
                 rest: Box::new(Term::Var {
                     name: "aa".to_owned(),
+                    span: S,
                 }),
+                span: S,
             },
         );
         test_parse(
@@ -401,15 +2033,484 @@ mod tests {
             Term::Const {
                 name: "ab".to_owned(),
                 init: Box::new(Term::If {
-                    cond: Box::new(Term::True),
-                    thn: Box::new(Term::Integer(7)),
-                    els: Box::new(Term::Integer(8)),
+                    cond: Box::new(Term::True(S)),
+                    thn: Box::new(Term::Number(7.0, S)),
+                    els: Box::new(Term::Number(8.0, S)),
+                    span: S,
                 }),
                 rest: Box::new(Term::Var {
                     name: "ab".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+    }
+
+    #[test]
+    fn test_type_decl() {
+        let point = Type::Object {
+            props: vec![
+                typ::Prop {
+                    name: "x".to_owned(),
+                    typ: Type::Number,
+                },
+                typ::Prop {
+                    name: "y".to_owned(),
+                    typ: Type::Number,
+                },
+            ],
+        };
+        test_parse(
+            "type Point = { x: number; y: number }; type P = Point | boolean; (p: P) => p;",
+            Term::TypeDecls {
+                decls: vec![
+                    TypeDecl {
+                        name: "Point".to_owned(),
+                        typ: (point, S),
+                        span: S,
+                    },
+                    TypeDecl {
+                        name: "P".to_owned(),
+                        typ: (
+                            Type::Union(vec![Type::Ref("Point".to_owned()), Type::Boolean]),
+                            S,
+                        ),
+                        span: S,
+                    },
+                ],
+                rest: Box::new(Term::Func {
+                    type_params: vec![],
+                    params: vec![Param {
+                        name: "p".to_owned(),
+                        typ: Some(Type::Ref("P".to_owned())),
+                        span: S,
+                    }],
+                    ret_type: None,
+                    body: Box::new(Term::Var {
+                        name: "p".to_owned(),
+                        span: S,
+                    }),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+
+        let Term::TypeDecls { decls, span, .. } = parse("type A = number;\n1;").unwrap() else {
+            panic!("type declarations expected");
+        };
+        assert_eq!(decls[0].typ.1.range(), 9..15);
+        assert_eq!(decls[0].span.range(), 0..16);
+        assert_eq!(span.range(), 0..18);
+
+        // 構文エラーのあった宣言は次の `;` まで読み飛ばす
+        let (term, errors) = parse_with_recovery("type A = ; type B = { a: ; b: number }; 1;");
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::UnexpectedToken {
+                    found: Token::Semicolon,
+                    expected: TYPE_EXPECTED.to_vec(),
+                    span: Span::new(9, 10),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::Semicolon,
+                    expected: TYPE_EXPECTED.to_vec(),
+                    span: Span::new(25, 26),
+                },
+            ]
+        );
+        let Term::Seq { body, rest, .. } = term else {
+            panic!("sequence expected");
+        };
+        assert_eq!(body.span().range(), 0..10);
+        assert_eq!(
+            *rest,
+            Term::Seq {
+                body: Box::new(Term::Error {
+                    span: Span::new(11, 39)
+                }),
+                rest: Box::new(Term::Number(1.0, Span::new(40, 41))),
+                span: Span::new(11, 41),
+            }
+        );
+
+        // 宣言で終わるプログラムの値は `undefined` である
+        let Term::TypeDecls { rest, .. } = parse("type A = number;").unwrap() else {
+            panic!("type declarations expected");
+        };
+        assert_eq!(
+            without_spans(*rest),
+            Term::Var {
+                name: "undefined".to_owned(),
+                span: S,
+            }
+        );
+
+        // `type` は宣言の始まりでなければ識別子として読み込む
+        test_parse(
+            "const type = 1; type;",
+            Term::Const {
+                name: "type".to_owned(),
+                init: Box::new(Term::Number(1.0, S)),
+                rest: Box::new(Term::Var {
+                    name: "type".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+    }
+
+    #[test]
+    fn test_generic() {
+        let var = |name: &str| Term::Var {
+            name: name.to_owned(),
+            span: S,
+        };
+        test_parse(
+            "<T, U>(x: T, y: U) => x",
+            Term::Func {
+                type_params: vec![
+                    TypeParam {
+                        name: "T".to_owned(),
+                        span: S,
+                    },
+                    TypeParam {
+                        name: "U".to_owned(),
+                        span: S,
+                    },
+                ],
+                params: vec![
+                    Param {
+                        name: "x".to_owned(),
+                        typ: Some(Type::Ref("T".to_owned())),
+                        span: S,
+                    },
+                    Param {
+                        name: "y".to_owned(),
+                        typ: Some(Type::Ref("U".to_owned())),
+                        span: S,
+                    },
+                ],
+                ret_type: None,
+                body: Box::new(var("x")),
+                span: S,
+            },
+        );
+        test_parse(
+            "f<number, { a: T }>(1)",
+            Term::Call {
+                func: Box::new(Term::Instantiate {
+                    func: Box::new(var("f")),
+                    type_args: vec![
+                        (Type::Number, S),
+                        (
+                            Type::Object {
+                                props: vec![typ::Prop {
+                                    name: "a".to_owned(),
+                                    typ: Type::Ref("T".to_owned()),
+                                }],
+                            },
+                            S,
+                        ),
+                    ],
+                    span: S,
+                }),
+                args: vec![Term::Number(1.0, S)],
+                span: S,
+            },
+        );
+
+        let Term::Func { params, span, .. } = parse("(f: <T>(x: T) => T) => f<boolean>").unwrap()
+        else {
+            panic!("function expected");
+        };
+        assert_eq!(
+            params[0].typ,
+            Some(Type::Generic {
+                type_params: vec![typ::TypeParam {
+                    name: "T".to_owned(),
+                    id: 0,
+                }],
+                func: Box::new(Type::Func {
+                    params: vec![typ::Param {
+                        name: "x".to_owned(),
+                        typ: Type::Ref("T".to_owned()),
+                    }],
+                    ret_type: Box::new(Type::Ref("T".to_owned())),
+                }),
+            })
+        );
+        assert_eq!(params[0].span.range(), 1..18);
+        assert_eq!(span.range(), 0..33);
+
+        assert_eq!(
+            parse("<>(x: number) => x"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Gt,
+                expected: vec!["identifier"],
+                span: Span::new(1, 2),
+            })
+        );
+        assert_eq!(
+            parse("<T> => 1"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Arrow,
+                expected: vec!["`(`"],
+                span: Span::new(4, 6),
+            })
+        );
+        let err = parse("f<number(1);").unwrap_err();
+        assert_eq!(err.to_string(), "`|` or `,` or `>` expected, but got `(`");
+        assert_eq!(err.span().range(), 8..9);
+    }
+
+    #[test]
+    fn test_func_decl() {
+        let var = |name: &str| Term::Var {
+            name: name.to_owned(),
+            span: S,
+        };
+        let param = |name: &str, typ: Type| Param {
+            name: name.to_owned(),
+            typ: Some(typ),
+            span: S,
+        };
+        test_parse(
+            "function f(x: number): number { return x; } f(1);",
+            Term::RecFunc {
+                funcs: vec![FuncDecl {
+                    name: "f".to_owned(),
+                    params: vec![param("x", Type::Number)],
+                    ret_type: (Type::Number, S),
+                    body: var("x"),
+                    span: S,
+                }],
+                rest: Box::new(Term::Call {
+                    func: Box::new(var("f")),
+                    args: vec![Term::Number(1.0, S)],
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+        test_parse(
+            "function f(): boolean { const x = true; return x } function g(): boolean { return f() } g();",
+            Term::RecFunc {
+                funcs: vec![
+                    FuncDecl {
+                        name: "f".to_owned(),
+                        params: vec![],
+                        ret_type: (Type::Boolean, S),
+                        body: Term::Const {
+                            name: "x".to_owned(),
+                            init: Box::new(Term::True(S)),
+                            rest: Box::new(var("x")),
+                            span: S,
+                        },
+                        span: S,
+                    },
+                    FuncDecl {
+                        name: "g".to_owned(),
+                        params: vec![],
+                        ret_type: (Type::Boolean, S),
+                        body: Term::Call {
+                            func: Box::new(var("f")),
+                            args: vec![],
+                            span: S,
+                        },
+                        span: S,
+                    },
+                ],
+                rest: Box::new(Term::Call {
+                    func: Box::new(var("g")),
+                    args: vec![],
+                    span: S,
+                }),
+                span: S,
+            },
+        );
+
+        let source = "1;\nfunction f(): number {\n  return 1;\n}\nf();";
+        let Term::Seq { rest, .. } = parse(source).unwrap() else {
+            panic!("seq expected");
+        };
+        let Term::RecFunc { funcs, span, .. } = *rest else {
+            panic!("function declaration expected");
+        };
+        assert_eq!(span.range(), 3..43);
+        assert_eq!(funcs[0].span.range(), 3..39);
+        assert_eq!(funcs[0].body.span().range(), 35..36);
+
+        // 本体の後に `;` を書いてもよい
+        test_parse(
+            "function f(): number { return 1; }; f();",
+            Term::RecFunc {
+                funcs: vec![FuncDecl {
+                    name: "f".to_owned(),
+                    params: vec![],
+                    ret_type: (Type::Number, S),
+                    body: Term::Number(1.0, S),
+                    span: S,
+                }],
+                rest: Box::new(Term::Call {
+                    func: Box::new(Term::Var {
+                        name: "f".to_owned(),
+                        span: S,
+                    }),
+                    args: vec![],
+                    span: S,
                 }),
+                span: S,
             },
         );
+
+        // 宣言で終わるプログラムの値は `undefined` である
+        let source = "function f(): number { return 1; }";
+        let Term::RecFunc { rest, span, .. } = parse(source).unwrap() else {
+            panic!("function declaration expected");
+        };
+        assert_eq!(
+            *rest,
+            Term::Var {
+                name: "undefined".to_owned(),
+                span: Span::new(34, 34),
+            }
+        );
+        assert_eq!(span.range(), 0..34);
+    }
+
+    #[test]
+    fn test_func_decl_error() {
+        assert_eq!(
+            parse("function f(x: number) { return x; } 1;"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::BraceL,
+                expected: vec!["`:`"],
+                span: Span::new(22, 23),
+            })
+        );
+        assert_eq!(
+            parse("return 1;"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Return,
+                expected: TERM_EXPECTED.to_vec(),
+                span: Span::new(0, 6),
+            })
+        );
+        assert_eq!(
+            parse("function f(): number { return 1 2 } f();"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Number(2.0),
+                expected: vec!["`+`", "`===`", "`?`", "`;`", "`}`"],
+                span: Span::new(32, 33),
+            })
+        );
+        assert_eq!(
+            parse("function f(x: number): number { x } f(1);"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::BraceR,
+                expected: vec!["`return`"],
+                span: Span::new(34, 35),
+            })
+        );
+        assert_eq!(
+            parse("function f(): number { const x = 1; x; } f();"),
+            Err(ParseError::UnexpectedToken {
+                found: Token::BraceR,
+                expected: vec!["`return`"],
+                span: Span::new(39, 40),
+            })
+        );
+        assert_eq!(
+            parse("function f(): number { return 1;"),
+            Err(ParseError::UnexpectedEof {
+                expected: vec!["`}`"],
+                span: Span::new(32, 32),
+            })
+        );
+
+        // 宣言の途中のエラーは宣言ごと読み飛ばす
+        let (term, errors) = parse_with_recovery(
+            "function f(x: 'abcd'): number { return x; } function g(): number { return 1; 2; } g();",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::UnexpectedToken {
+                    found: Token::String("abcd".to_owned()),
+                    expected: TYPE_EXPECTED.to_vec(),
+                    span: Span::new(14, 20),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::Number(2.0),
+                    expected: vec!["`}`"],
+                    span: Span::new(77, 78),
+                },
+            ]
+        );
+        assert_eq!(
+            without_spans(term),
+            Term::Seq {
+                body: Box::new(Term::Error { span: S }),
+                rest: Box::new(Term::RecFunc {
+                    funcs: vec![FuncDecl {
+                        name: "g".to_owned(),
+                        params: vec![],
+                        ret_type: (Type::Number, S),
+                        body: Term::Number(1.0, S),
+                        span: S,
+                    }],
+                    rest: Box::new(Term::Call {
+                        func: Box::new(Term::Var {
+                            name: "g".to_owned(),
+                            span: S,
+                        }),
+                        args: vec![],
+                        span: S,
+                    }),
+                    span: S,
+                }),
+                span: S,
+            }
+        );
+        assert_eq!(errors[0].span().range(), 14..20);
+
+        // 本体の中のエラーは本体の中で回復する
+        let (term, errors) = parse_with_recovery("function f(): number { 1 + ; return (2 } f();");
+        assert_eq!(errors.len(), 2);
+        let Term::RecFunc { funcs, .. } = term else {
+            panic!("function declaration expected");
+        };
+        assert_eq!(
+            funcs[0].body,
+            Term::Seq {
+                body: Box::new(Term::Error {
+                    span: Span::new(23, 28)
+                }),
+                rest: Box::new(Term::Error {
+                    span: Span::new(29, 38)
+                }),
+                span: Span::new(23, 38),
+            }
+        );
+        // `return` 文の後の対応しない `)` も本体の中で読み飛ばす
+        let (term, errors) = parse_with_recovery("function f(): number { return 1; ) } f();");
+        assert_eq!(
+            errors,
+            vec![ParseError::UnexpectedToken {
+                found: Token::ParenR,
+                expected: vec!["`}`"],
+                span: Span::new(33, 34),
+            }]
+        );
+        let Term::RecFunc { funcs, rest, .. } = term else {
+            panic!("function declaration expected");
+        };
+        assert_eq!(funcs[0].body, Term::Number(1.0, Span::new(30, 31)));
+        assert_eq!(rest.span().range(), 37..40);
     }
 
     #[test]
@@ -417,44 +2518,278 @@ mod tests {
         test_parse(
             "1; 2;",
             Term::Seq {
-                body: Box::new(Term::Integer(1)),
-                rest: Box::new(Term::Integer(2)),
+                body: Box::new(Term::Number(1.0, S)),
+                rest: Box::new(Term::Number(2.0, S)),
+                span: S,
             },
         );
         test_parse(
             "3; 4; 5;",
             Term::Seq {
-                body: Box::new(Term::Integer(3)),
+                body: Box::new(Term::Number(3.0, S)),
                 rest: Box::new(Term::Seq {
-                    body: Box::new(Term::Integer(4)),
-                    rest: Box::new(Term::Integer(5)),
+                    body: Box::new(Term::Number(4.0, S)),
+                    rest: Box::new(Term::Number(5.0, S)),
+                    span: S,
                 }),
+                span: S,
             },
         );
         test_parse(
             "6; 7",
             Term::Seq {
-                body: Box::new(Term::Integer(6)),
-                rest: Box::new(Term::Integer(7)),
+                body: Box::new(Term::Number(6.0, S)),
+                rest: Box::new(Term::Number(7.0, S)),
+                span: S,
             },
         );
     }
 
     #[test]
     fn test_term() {
-        test_parse("0;", Term::Integer(0));
+        test_parse("0;", Term::Number(0.0, S));
         test_parse(
             "const x = 1; x; 2",
             Term::Const {
                 name: "x".to_owned(),
-                init: Box::new(Term::Integer(1)),
+                init: Box::new(Term::Number(1.0, S)),
                 rest: Box::new(Term::Seq {
                     body: Box::new(Term::Var {
                         name: "x".to_owned(),
+                        span: S,
                     }),
-                    rest: Box::new(Term::Integer(2)),
+                    rest: Box::new(Term::Number(2.0, S)),
+                    span: S,
                 }),
+                span: S,
             },
         );
     }
+
+    #[test]
+    fn test_span() {
+        let term = parse("const f = (x: number) => x + 1; f()").unwrap();
+        assert_eq!(term.span().range(), 0..35);
+        let Term::Const { init, rest, .. } = term else {
+            panic!()
+        };
+        assert_eq!(init.span().range(), 10..30);
+        let Term::Func { params, body, .. } = *init else {
+            panic!()
+        };
+        assert_eq!(params[0].span.range(), 11..20);
+        assert_eq!(body.span().range(), 25..30);
+        assert_eq!(rest.span().range(), 32..35);
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(
+            without_spans(parse("const = 1; 2")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Equals,
+                expected: vec!["identifier"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("(x: number) =>")),
+            Err(ParseError::UnexpectedEof {
+                expected: PRIMARY_EXPR_EXPECTED.to_vec(),
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("(x: 'abcd') => x")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::String("abcd".to_owned()),
+                expected: TYPE_EXPECTED.to_vec(),
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("(x: number y: boolean) => x")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Ident("y".to_owned()),
+                expected: vec!["`,`", "`)`"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("1 2")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Number(2.0),
+                expected: vec!["`+`", "`===`", "`?`", "`;`", "end of input"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("const x = 1;")),
+            Err(ParseError::UnexpectedEof {
+                expected: vec![
+                    "`false`",
+                    "`true`",
+                    "number",
+                    "string",
+                    "identifier",
+                    "`typeof`",
+                    "`(`",
+                    "`<`",
+                    "`const`",
+                    "`function`"
+                ],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("f(1 2)")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Number(2.0),
+                expected: vec!["`+`", "`===`", "`?`", "`,`", "`)`"],
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("f(,)")),
+            Err(ParseError::UnexpectedToken {
+                found: Token::Comma,
+                expected: ARGS_EXPECTED.to_vec(),
+                span: S,
+            })
+        );
+        assert_eq!(
+            without_spans(parse("0x")),
+            Err(ParseError::InvalidToken {
+                text: "0x".to_owned(),
+                span: S,
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_message() {
+        let err = parse("const x = 1 x").unwrap_err();
+        assert_eq!(err.span().range(), 12..13);
+        assert_eq!(err.to_string(), "`;` expected, but got `x`");
+
+        let err = parse("true ? 1").unwrap_err();
+        assert_eq!(err.span().range(), 8..8);
+        assert_eq!(err.to_string(), "`:` expected, but got end of input");
+
+        let err = parse("1 + @").unwrap_err();
+        assert_eq!(err.span().range(), 4..5);
+        assert_eq!(err.to_string(), "invalid token `@`");
+    }
+
+    #[test]
+    fn test_recovery() {
+        let (term, errors) =
+            parse_with_recovery("1 +; 2 3; const = 4; const x = (y: 'abcd') => y; x; 5");
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::UnexpectedToken {
+                    found: Token::Semicolon,
+                    expected: PRIMARY_EXPR_EXPECTED.to_vec(),
+                    span: Span::new(3, 4),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::Number(3.0),
+                    expected: vec!["`+`", "`===`", "`?`", "`;`", "end of input"],
+                    span: Span::new(7, 8),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::Equals,
+                    expected: vec!["identifier"],
+                    span: Span::new(16, 17),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::String("abcd".to_owned()),
+                    expected: TYPE_EXPECTED.to_vec(),
+                    span: Span::new(35, 41),
+                },
+            ]
+        );
+        assert_eq!(
+            without_spans(term),
+            Term::Seq {
+                body: Box::new(Term::Error { span: S }),
+                rest: Box::new(Term::Seq {
+                    body: Box::new(Term::Error { span: S }),
+                    rest: Box::new(Term::Seq {
+                        body: Box::new(Term::Error { span: S }),
+                        rest: Box::new(Term::Const {
+                            name: "x".to_owned(),
+                            init: Box::new(Term::Error { span: S }),
+                            rest: Box::new(Term::Seq {
+                                body: Box::new(Term::Var {
+                                    name: "x".to_owned(),
+                                    span: S,
+                                }),
+                                rest: Box::new(Term::Number(5.0, S)),
+                                span: S,
+                            }),
+                            span: S,
+                        }),
+                        span: S,
+                    }),
+                    span: S,
+                }),
+                span: S,
+            }
+        );
+    }
+
+    #[test]
+    fn test_recovery_in_paren() {
+        let (term, errors) = parse_with_recovery("f(1 + 2 3) + g(;");
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::UnexpectedToken {
+                    found: Token::Number(3.0),
+                    expected: vec!["`+`", "`===`", "`?`", "`,`", "`)`"],
+                    span: Span::new(8, 9),
+                },
+                ParseError::UnexpectedToken {
+                    found: Token::Semicolon,
+                    expected: ARGS_EXPECTED.to_vec(),
+                    span: Span::new(15, 16),
+                },
+            ]
+        );
+        assert_eq!(term.span().range(), 0..15);
+        assert_eq!(
+            without_spans(term),
+            Term::Add {
+                left: Box::new(Term::Error { span: S }),
+                right: Box::new(Term::Error { span: S }),
+                span: S,
+            }
+        );
+
+        let (term, errors) = parse_with_recovery("const x = 1 2; x");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            without_spans(term),
+            Term::Const {
+                name: "x".to_owned(),
+                init: Box::new(Term::Error { span: S }),
+                rest: Box::new(Term::Var {
+                    name: "x".to_owned(),
+                    span: S,
+                }),
+                span: S,
+            }
+        );
+
+        let (_, errors) = parse_with_recovery("const x = 1;");
+        assert_eq!(
+            errors,
+            vec![ParseError::UnexpectedEof {
+                expected: TERM_EXPECTED.to_vec(),
+                span: Span::new(12, 12),
+            }]
+        );
+    }
 }
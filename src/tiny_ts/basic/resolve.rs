@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use crate::tiny_ts::basic::term::{self, FuncDecl, TypeDecl};
+use crate::tiny_ts::basic::typ::{Param, Prop, Type, TypeParam};
+use crate::tiny_ts::basic::typecheck::TypeError;
+use crate::tiny_ts::span::Span;
+
+/// 型の名前から、それが表す型 (型エイリアスの `Type::Alias` または型引数の `Type::Var`) への対応
+pub(super) type TypeNames = HashMap<String, Type>;
+
+/// 型注釈の解決 (型検査と型推論で共通)
+///
+/// 型注釈のジェネリック関数の型と、型注釈を省略した引数の扱いだけが異なる
+pub(super) trait Resolve {
+    /// 報告した型エラー
+    fn errors(&mut self) -> &mut Vec<TypeError>;
+
+    /// 型エイリアスが別名を付けた型 (`Type::Alias` の `id` 番目)
+    fn aliases(&mut self) -> &mut Vec<Type>;
+
+    /// 型注釈のジェネリック関数の型 `<type_params>func` を解決する
+    fn resolve_generic(
+        &mut self,
+        type_params: &[TypeParam],
+        func: &Type,
+        span: Span,
+        types: &TypeNames,
+    ) -> Type;
+
+    /// 型注釈を省略した引数 `param` の型
+    fn missing_param_type(&mut self, param: &term::Param) -> Type;
+
+    /// 型注釈の中の型の名前 (`Type::Ref`) を、`types` で見える宣言の `Type::Alias` や `Type::Var` に置き換える
+    ///
+    /// 宣言されていない名前は `span` の位置のエラーとして報告し、`Type::Unknown` とする
+    fn resolve(&mut self, typ: &Type, span: Span, types: &TypeNames) -> Type {
+        match typ {
+            Type::Ref(name) => match types.get(name) {
+                Some(typ) => typ.clone(),
+                None => {
+                    self.errors().push(TypeError::UnknownType {
+                        name: name.clone(),
+                        span,
+                    });
+                    Type::Unknown
+                }
+            },
+            Type::Func { params, ret_type } => Type::Func {
+                params: params
+                    .iter()
+                    .map(|param| Param {
+                        name: param.name.clone(),
+                        typ: self.resolve(&param.typ, span, types),
+                    })
+                    .collect(),
+                ret_type: Box::new(self.resolve(ret_type, span, types)),
+            },
+            Type::Generic { type_params, func } => {
+                self.resolve_generic(type_params, func, span, types)
+            }
+            Type::Object { props } => Type::Object {
+                props: props
+                    .iter()
+                    .map(|prop| Prop {
+                        name: prop.name.clone(),
+                        typ: self.resolve(&prop.typ, span, types),
+                    })
+                    .collect(),
+            },
+            Type::Union(members) => {
+                let members = members
+                    .iter()
+                    .map(|typ| self.resolve(typ, span, types))
+                    .collect::<Vec<_>>();
+                Type::union(members)
+            }
+            Type::Boolean
+            | Type::Number
+            | Type::String
+            | Type::Undefined
+            | Type::Var { .. }
+            | Type::Alias { .. }
+            | Type::Unknown => typ.clone(),
+        }
+    }
+
+    /// 関数の引数の型注釈を解決し、関数型の引数にする
+    fn resolve_param(&mut self, param: &term::Param, types: &TypeNames) -> Param {
+        let typ = match &param.typ {
+            Some(typ) => self.resolve(typ, param.span, types),
+            None => self.missing_param_type(param),
+        };
+        Param {
+            name: param.name.clone(),
+            typ,
+        }
+    }
+
+    /// 関数宣言の引数と戻り値の型注釈を解決する
+    fn resolve_signature(&mut self, func: &FuncDecl, types: &TypeNames) -> (Vec<Param>, Type) {
+        let params = func
+            .params
+            .iter()
+            .map(|param| self.resolve_param(param, types))
+            .collect();
+        let (ret_type, annotation_span) = &func.ret_type;
+        (params, self.resolve(ret_type, *annotation_span, types))
+    }
+
+    /// 型エイリアスの宣言の並びを `types` に加え、それぞれの定義を解決する
+    ///
+    /// 定義を解決する前に全ての名前を加えるため、宣言どうしは互いに参照できる。
+    /// 見えている型と同じ名前の宣言は、番号を付けた名前で表示して区別する (例: 2 つ目の `T` は `T1`)。
+    /// オブジェクト型や関数型を挟まずに自分自身を参照する型エイリアスは展開できないため、
+    /// エラーとして報告し `Type::Unknown` に置き換える
+    fn declare_type_aliases(&mut self, decls: &[TypeDecl], types: &mut TypeNames) {
+        let mut ids = vec![];
+        for decl in decls {
+            let id = self.aliases().len();
+            self.aliases().push(Type::Unknown);
+            let mut name = decl.name.clone();
+            let mut n = 0;
+            while types.contains_key(&name)
+                || types.values().any(|typ| shown_as(typ, &name))
+                || (n > 0 && decls.iter().any(|decl| decl.name == name))
+            {
+                n += 1;
+                name = format!("{}{}", decl.name, n);
+            }
+            types.insert(decl.name.clone(), Type::Alias { name, id });
+            ids.push(id);
+        }
+        for (decl, &id) in decls.iter().zip(&ids) {
+            let (typ, span) = &decl.typ;
+            self.aliases()[id] = self.resolve(typ, *span, types);
+        }
+        // 置き換える前に全て調べ、同じ循環に含まれる型エイリアスを全て報告する
+        let aliases = self.aliases();
+        let circular = decls
+            .iter()
+            .zip(&ids)
+            .filter(|&(_, &id)| refers_to(aliases, &aliases[id], id, &mut vec![]))
+            .collect::<Vec<_>>();
+        for (decl, &id) in circular {
+            self.errors().push(TypeError::CircularTypeAlias {
+                name: decl.name.clone(),
+                span: decl.span,
+            });
+            self.aliases()[id] = Type::Unknown;
+        }
+    }
+}
+
+/// 型の名前で表される型 `typ` が `name` と表示されるか
+fn shown_as(typ: &Type, name: &str) -> bool {
+    matches!(typ, Type::Alias { name: shown, .. } | Type::Var { name: shown, .. } if shown == name)
+}
+
+/// 型エイリアスの定義 (`aliases`) を辿ったとき、オブジェクト型や関数型の中に入らずに `id` 番目の型エイリアスに達するか
+///
+/// オブジェクト型や関数型の中での参照は展開を一段ずつ遅らせられるため、再帰的な型として許す
+fn refers_to(aliases: &[Type], typ: &Type, id: usize, visited: &mut Vec<usize>) -> bool {
+    match typ {
+        Type::Alias { id: other, .. } => {
+            if *other == id {
+                return true;
+            }
+            if visited.contains(other) {
+                return false;
+            }
+            visited.push(*other);
+            refers_to(aliases, &aliases[*other], id, visited)
+        }
+        Type::Union(types) => types.iter().any(|typ| refers_to(aliases, typ, id, visited)),
+        Type::Boolean
+        | Type::Number
+        | Type::String
+        | Type::Undefined
+        | Type::Func { .. }
+        | Type::Generic { .. }
+        | Type::Var { .. }
+        | Type::Object { .. }
+        | Type::Ref(_)
+        | Type::Unknown => false,
+    }
+}
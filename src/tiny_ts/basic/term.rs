@@ -1,40 +1,294 @@
 use super::Type;
+#[cfg(test)]
+use crate::tiny_ts::span::ClearSpans;
+use crate::tiny_ts::span::Span;
 
 #[derive(Debug, PartialEq)]
 pub enum Term {
     /// false リテラル (例: `false`)
-    False,
+    False(Span),
     /// true リテラル (例: `true`)
-    True,
-    /// 数値リテラル (例: `1`, `2`, `100`)
-    /// 実装の簡素化のため 0..=255 の範囲に制限
-    Integer(u8),
+    True(Span),
+    /// 数値リテラル (例: `1`, `0.5`, `1_000`, `0xff`, `0b1010`)
+    /// JavaScript と同じく倍精度浮動小数点数として扱う
+    Number(f64, Span),
+    /// 文字列リテラル (例: `"number"`, `'a'`)
+    String(String, Span),
     /// 足し算 (例: `1 + 2`)
-    Add { left: Box<Term>, right: Box<Term> },
+    Add {
+        left: Box<Term>,
+        right: Box<Term>,
+        span: Span,
+    },
+    /// 厳密等価演算子 (例: `x === 1`, `typeof x === "number"`)
+    StrictEq {
+        left: Box<Term>,
+        right: Box<Term>,
+        span: Span,
+    },
+    /// 値の種類を表す文字列 (例: `typeof x`)
+    TypeOf { expr: Box<Term>, span: Span },
     /// 条件演算子 (例: `false ? 1 : 2`, `true ? false : true`)
     If {
         cond: Box<Term>,
         thn: Box<Term>,
         els: Box<Term>,
+        span: Span,
     },
     /// 変数参照 (例: `x`, `f`)
-    Var { name: String },
-    /// 無名関数 (例: `(x: number) => x`)
-    Func { params: Vec<Param>, body: Box<Term> },
+    Var { name: String, span: Span },
+    /// 無名関数 (例: `(x: number) => x`, `(x: number): number => x`, `<T>(x: T) => x`, `(x) => x`)
+    Func {
+        /// 型引数の宣言 (ジェネリック関数でなければ空)
+        type_params: Vec<TypeParam>,
+        params: Vec<Param>,
+        /// 戻り値の型注釈とその位置 (省略した場合は本体の型を戻り値の型とする)
+        ret_type: Option<(Type, Span)>,
+        body: Box<Term>,
+        span: Span,
+    },
     /// 関数呼び出し (例: `f(1)`, `f(true)`)
-    Call { func: Box<Term>, args: Vec<Term> },
+    Call {
+        func: Box<Term>,
+        args: Vec<Term>,
+        span: Span,
+    },
+    /// ジェネリック関数の型引数の指定 (例: `f<number>`, `f<number>(1)` の `f<number>` の部分)
+    Instantiate {
+        func: Box<Term>,
+        /// 型引数とその位置
+        type_args: Vec<(Type, Span)>,
+        span: Span,
+    },
+    /// オブジェクトリテラル (例: `{ a: 1, b: true }`)
+    Object {
+        props: Vec<(String, Term)>,
+        span: Span,
+    },
+    /// プロパティの参照 (例: `o.a`)
+    Get {
+        obj: Box<Term>,
+        name: String,
+        span: Span,
+    },
     /// 逐次実行 (例: `f(1); f(2);`)
-    Seq { body: Box<Term>, rest: Box<Term> },
+    Seq {
+        body: Box<Term>,
+        rest: Box<Term>,
+        span: Span,
+    },
     /// 変数定義 (例: `const x = 1; ...`)
     Const {
         name: String,
         init: Box<Term>,
         rest: Box<Term>,
+        span: Span,
     },
+    /// 関数宣言 (例: `function f(x: number): number { return x; } ...`)
+    /// 連続する関数宣言は互いに (自分自身も) 参照できる
+    RecFunc {
+        funcs: Vec<FuncDecl>,
+        rest: Box<Term>,
+        span: Span,
+    },
+    /// 型エイリアスの宣言 (例: `type Point = { x: number; y: number }; ...`)
+    /// 連続する型エイリアスの宣言は互いに参照できる
+    TypeDecls {
+        decls: Vec<TypeDecl>,
+        rest: Box<Term>,
+        span: Span,
+    },
+    /// 構文エラーのため読み飛ばした部分
+    Error { span: Span },
+}
+
+impl Term {
+    pub fn span(&self) -> Span {
+        match self {
+            Term::False(span)
+            | Term::True(span)
+            | Term::Number(_, span)
+            | Term::String(_, span) => *span,
+            Term::Add { span, .. }
+            | Term::StrictEq { span, .. }
+            | Term::TypeOf { span, .. }
+            | Term::If { span, .. }
+            | Term::Var { span, .. }
+            | Term::Func { span, .. }
+            | Term::Call { span, .. }
+            | Term::Instantiate { span, .. }
+            | Term::Object { span, .. }
+            | Term::Get { span, .. }
+            | Term::Seq { span, .. }
+            | Term::Const { span, .. }
+            | Term::RecFunc { span, .. }
+            | Term::TypeDecls { span, .. }
+            | Term::Error { span } => *span,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for Term {
+    fn clear_spans(&mut self) {
+        match self {
+            Term::False(span)
+            | Term::True(span)
+            | Term::Number(_, span)
+            | Term::String(_, span)
+            | Term::Var { span, .. }
+            | Term::Error { span } => span.clear_spans(),
+            Term::Add { left, right, span } | Term::StrictEq { left, right, span } => {
+                left.clear_spans();
+                right.clear_spans();
+                span.clear_spans();
+            }
+            Term::TypeOf { expr, span } => {
+                expr.clear_spans();
+                span.clear_spans();
+            }
+            Term::If {
+                cond,
+                thn,
+                els,
+                span,
+            } => {
+                cond.clear_spans();
+                thn.clear_spans();
+                els.clear_spans();
+                span.clear_spans();
+            }
+            Term::Func {
+                type_params,
+                params,
+                ret_type,
+                body,
+                span,
+            } => {
+                type_params.clear_spans();
+                params.clear_spans();
+                if let Some((_, ret_span)) = ret_type {
+                    ret_span.clear_spans();
+                }
+                body.clear_spans();
+                span.clear_spans();
+            }
+            Term::Call { func, args, span } => {
+                func.clear_spans();
+                args.clear_spans();
+                span.clear_spans();
+            }
+            Term::Instantiate {
+                func,
+                type_args,
+                span,
+            } => {
+                func.clear_spans();
+                for (_, arg_span) in type_args {
+                    arg_span.clear_spans();
+                }
+                span.clear_spans();
+            }
+            Term::Object { props, span } => {
+                for (_, value) in props {
+                    value.clear_spans();
+                }
+                span.clear_spans();
+            }
+            Term::Get { obj, span, .. } => {
+                obj.clear_spans();
+                span.clear_spans();
+            }
+            Term::Seq { body, rest, span } => {
+                body.clear_spans();
+                rest.clear_spans();
+                span.clear_spans();
+            }
+            Term::Const {
+                init, rest, span, ..
+            } => {
+                init.clear_spans();
+                rest.clear_spans();
+                span.clear_spans();
+            }
+            Term::RecFunc { funcs, rest, span } => {
+                funcs.clear_spans();
+                rest.clear_spans();
+                span.clear_spans();
+            }
+            Term::TypeDecls { decls, rest, span } => {
+                decls.clear_spans();
+                rest.clear_spans();
+                span.clear_spans();
+            }
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Param {
     pub name: String,
-    pub typ: Type,
+    /// 型注釈 (省略した場合は `None`)
+    pub typ: Option<Type>,
+    pub span: Span,
+}
+
+/// 型引数の宣言 (例: `<T, U>(x: T, y: U) => x` の `T` や `U`)
+#[derive(Debug, PartialEq)]
+pub struct TypeParam {
+    pub name: String,
+    pub span: Span,
+}
+
+/// `function` による関数宣言 (戻り値の型注釈は必須)
+#[derive(Debug, PartialEq)]
+pub struct FuncDecl {
+    pub name: String,
+    pub params: Vec<Param>,
+    /// 戻り値の型注釈とその位置
+    pub ret_type: (Type, Span),
+    /// 関数本体の文の並び (`return` 文の式が最後の文になる)
+    pub body: Term,
+    pub span: Span,
+}
+
+/// `type` による型エイリアスの宣言
+#[derive(Debug, PartialEq)]
+pub struct TypeDecl {
+    pub name: String,
+    /// 別名を付ける型とその位置
+    pub typ: (Type, Span),
+    pub span: Span,
+}
+
+#[cfg(test)]
+impl ClearSpans for Param {
+    fn clear_spans(&mut self) {
+        self.span.clear_spans();
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for TypeParam {
+    fn clear_spans(&mut self) {
+        self.span.clear_spans();
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for FuncDecl {
+    fn clear_spans(&mut self) {
+        self.params.clear_spans();
+        self.ret_type.1.clear_spans();
+        self.body.clear_spans();
+        self.span.clear_spans();
+    }
+}
+
+#[cfg(test)]
+impl ClearSpans for TypeDecl {
+    fn clear_spans(&mut self) {
+        self.typ.1.clear_spans();
+        self.span.clear_spans();
+    }
 }
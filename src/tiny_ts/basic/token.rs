@@ -1,12 +1,16 @@
-#[derive(Debug, PartialEq, logos::Logos)]
+use crate::tiny_ts::number::parse_number;
+
+#[derive(Clone, Debug, PartialEq, logos::Logos)]
 #[logos(skip r"\s+")]
 pub enum Token {
     #[token("false")]
     False,
     #[token("true")]
     True,
-    #[regex("0|[1-9][0-9]{0,}", |lexer| lexer.slice().parse::<u8>().ok())]
-    Integer(u8),
+    #[regex(r"(0|[1-9](_?[0-9])*)(\.[0-9](_?[0-9])*)?", |lexer| parse_number(lexer.slice(), 10))]
+    #[regex(r"0[xX][0-9a-fA-F](_?[0-9a-fA-F])*", |lexer| parse_number(&lexer.slice()[2..], 16))]
+    #[regex(r"0[bB][01](_?[01])*", |lexer| parse_number(&lexer.slice()[2..], 2))]
+    Number(f64),
     #[token("+")]
     Plus,
     #[token("?")]
@@ -29,6 +33,64 @@ pub enum Token {
     ParenR,
     #[token("=>")]
     Arrow,
+    #[token("function")]
+    Function,
+    #[token("return")]
+    Return,
+    #[token("{")]
+    BraceL,
+    #[token("}")]
+    BraceR,
+    #[token(".")]
+    Dot,
+    /// 文字列リテラル (エスケープシーケンスには対応しない)
+    #[regex(r#""[^"\n]*"|'[^'\n]*'"#, |lexer| {
+        let slice = lexer.slice();
+        slice[1..slice.len() - 1].to_string()
+    })]
+    String(String),
+    #[token("typeof")]
+    TypeOf,
+    #[token("===")]
+    StrictEquals,
+    #[token("|")]
+    Pipe,
+    #[token("<")]
+    Lt,
+    #[token(">")]
+    Gt,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::False => write!(f, "false"),
+            Token::True => write!(f, "true"),
+            Token::Number(i) => write!(f, "{}", i),
+            Token::Plus => write!(f, "+"),
+            Token::Quest => write!(f, "?"),
+            Token::Colon => write!(f, ":"),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Semicolon => write!(f, ";"),
+            Token::Const => write!(f, "const"),
+            Token::Equals => write!(f, "="),
+            Token::ParenL => write!(f, "("),
+            Token::Comma => write!(f, ","),
+            Token::ParenR => write!(f, ")"),
+            Token::Arrow => write!(f, "=>"),
+            Token::Function => write!(f, "function"),
+            Token::Return => write!(f, "return"),
+            Token::BraceL => write!(f, "{{"),
+            Token::BraceR => write!(f, "}}"),
+            Token::Dot => write!(f, "."),
+            Token::String(s) => write!(f, "\"{}\"", s),
+            Token::TypeOf => write!(f, "typeof"),
+            Token::StrictEquals => write!(f, "==="),
+            Token::Pipe => write!(f, "|"),
+            Token::Lt => write!(f, "<"),
+            Token::Gt => write!(f, ">"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -47,7 +109,7 @@ mod tests {
         assert_eq!(lexer.span(), 5..6);
         assert_eq!(lexer.slice(), "?");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(0))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(0.0))));
         assert_eq!(lexer.span(), 7..8);
         assert_eq!(lexer.slice(), "0");
 
@@ -55,7 +117,7 @@ mod tests {
         assert_eq!(lexer.span(), 9..10);
         assert_eq!(lexer.slice(), ":");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(1))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(1.0))));
         assert_eq!(lexer.span(), 11..12);
         assert_eq!(lexer.slice(), "1");
 
@@ -63,7 +125,7 @@ mod tests {
         assert_eq!(lexer.span(), 13..14);
         assert_eq!(lexer.slice(), "+");
 
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(2))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(2.0))));
         assert_eq!(lexer.span(), 15..16);
         assert_eq!(lexer.slice(), "2");
 
@@ -115,7 +177,7 @@ mod tests {
         assert_eq!(lexer.next(), Some(Ok(Token::ParenL)));
         assert_eq!(lexer.span(), 1..2);
         assert_eq!(lexer.slice(), "(");
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(1))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(1.0))));
         assert_eq!(lexer.span(), 2..3);
         assert_eq!(lexer.slice(), "1");
         assert_eq!(lexer.next(), Some(Ok(Token::ParenR)));
@@ -130,13 +192,13 @@ mod tests {
         assert_eq!(lexer.next(), Some(Ok(Token::ParenL)));
         assert_eq!(lexer.span(), 1..2);
         assert_eq!(lexer.slice(), "(");
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(2))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(2.0))));
         assert_eq!(lexer.span(), 2..3);
         assert_eq!(lexer.slice(), "2");
         assert_eq!(lexer.next(), Some(Ok(Token::Comma)));
         assert_eq!(lexer.span(), 3..4);
         assert_eq!(lexer.slice(), ",");
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(3))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(3.0))));
         assert_eq!(lexer.span(), 5..6);
         assert_eq!(lexer.slice(), "3");
         assert_eq!(lexer.next(), Some(Ok(Token::ParenR)));
@@ -154,7 +216,7 @@ mod tests {
         assert_eq!(lexer.next(), Some(Ok(Token::Equals)));
         assert_eq!(lexer.span(), 8..9);
         assert_eq!(lexer.slice(), "=");
-        assert_eq!(lexer.next(), Some(Ok(Token::Integer(1))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Number(1.0))));
         assert_eq!(lexer.span(), 10..11);
         assert_eq!(lexer.slice(), "1");
         assert_eq!(lexer.next(), Some(Ok(Token::Semicolon)));
@@ -165,4 +227,137 @@ mod tests {
         assert_eq!(lexer.slice(), "x");
         assert_eq!(lexer.next(), None);
     }
+
+    #[test]
+    fn test_function() {
+        let lexer = <Token as logos::Logos>::lexer("function functions(): number { return 1; }");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::Function), 0..8),
+                (Ok(Token::Ident("functions".to_owned())), 9..18),
+                (Ok(Token::ParenL), 18..19),
+                (Ok(Token::ParenR), 19..20),
+                (Ok(Token::Colon), 20..21),
+                (Ok(Token::Ident("number".to_owned())), 22..28),
+                (Ok(Token::BraceL), 29..30),
+                (Ok(Token::Return), 31..37),
+                (Ok(Token::Number(1.0)), 38..39),
+                (Ok(Token::Semicolon), 39..40),
+                (Ok(Token::BraceR), 41..42),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_object() {
+        let lexer = <Token as logos::Logos>::lexer("{ a: 1 }.a");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::BraceL), 0..1),
+                (Ok(Token::Ident("a".to_owned())), 2..3),
+                (Ok(Token::Colon), 3..4),
+                (Ok(Token::Number(1.0)), 5..6),
+                (Ok(Token::BraceR), 7..8),
+                (Ok(Token::Dot), 8..9),
+                (Ok(Token::Ident("a".to_owned())), 9..10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_union() {
+        let lexer = <Token as logos::Logos>::lexer(
+            "(x: number | string) => typeof x === \"number\" ? x : 'typeof'",
+        );
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::ParenL), 0..1),
+                (Ok(Token::Ident("x".to_owned())), 1..2),
+                (Ok(Token::Colon), 2..3),
+                (Ok(Token::Ident("number".to_owned())), 4..10),
+                (Ok(Token::Pipe), 11..12),
+                (Ok(Token::Ident("string".to_owned())), 13..19),
+                (Ok(Token::ParenR), 19..20),
+                (Ok(Token::Arrow), 21..23),
+                (Ok(Token::TypeOf), 24..30),
+                (Ok(Token::Ident("x".to_owned())), 31..32),
+                (Ok(Token::StrictEquals), 33..36),
+                (Ok(Token::String("number".to_owned())), 37..45),
+                (Ok(Token::Quest), 46..47),
+                (Ok(Token::Ident("x".to_owned())), 48..49),
+                (Ok(Token::Colon), 50..51),
+                (Ok(Token::String("typeof".to_owned())), 52..60),
+            ]
+        );
+
+        // 閉じられていない文字列は字句解析できない
+        let mut lexer = <Token as logos::Logos>::lexer("\"abc");
+        assert_eq!(lexer.next(), Some(Err(())));
+    }
+
+    #[test]
+    fn test_type_alias() {
+        let lexer = <Token as logos::Logos>::lexer("type types = { type: number };");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                // `type` は文脈依存のキーワードであり、識別子として読み込む
+                (Ok(Token::Ident("type".to_owned())), 0..4),
+                (Ok(Token::Ident("types".to_owned())), 5..10),
+                (Ok(Token::Equals), 11..12),
+                (Ok(Token::BraceL), 13..14),
+                (Ok(Token::Ident("type".to_owned())), 15..19),
+                (Ok(Token::Colon), 19..20),
+                (Ok(Token::Ident("number".to_owned())), 21..27),
+                (Ok(Token::BraceR), 28..29),
+                (Ok(Token::Semicolon), 29..30),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generic() {
+        let lexer = <Token as logos::Logos>::lexer("<T>(x: T) => f<number>(x)");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::Lt), 0..1),
+                (Ok(Token::Ident("T".to_owned())), 1..2),
+                (Ok(Token::Gt), 2..3),
+                (Ok(Token::ParenL), 3..4),
+                (Ok(Token::Ident("x".to_owned())), 4..5),
+                (Ok(Token::Colon), 5..6),
+                (Ok(Token::Ident("T".to_owned())), 7..8),
+                (Ok(Token::ParenR), 8..9),
+                (Ok(Token::Arrow), 10..12),
+                (Ok(Token::Ident("f".to_owned())), 13..14),
+                (Ok(Token::Lt), 14..15),
+                (Ok(Token::Ident("number".to_owned())), 15..21),
+                (Ok(Token::Gt), 21..22),
+                (Ok(Token::ParenL), 22..23),
+                (Ok(Token::Ident("x".to_owned())), 23..24),
+                (Ok(Token::ParenR), 24..25),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        let lexer = <Token as logos::Logos>::lexer("256 1_000.5 0xFf 0B1_01 o.x");
+        assert_eq!(
+            lexer.spanned().collect::<Vec<_>>(),
+            vec![
+                (Ok(Token::Number(256.0)), 0..3),
+                (Ok(Token::Number(1000.5)), 4..11),
+                (Ok(Token::Number(255.0)), 12..16),
+                (Ok(Token::Number(5.0)), 17..23),
+                (Ok(Token::Ident("o".to_owned())), 24..25),
+                (Ok(Token::Dot), 25..26),
+                (Ok(Token::Ident("x".to_owned())), 26..27),
+            ]
+        );
+    }
 }
@@ -1,15 +1,201 @@
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Type {
     Boolean,
-    Integer,
+    Number,
+    String,
+    Undefined,
     Func {
         params: Vec<Param>,
         ret_type: Box<Type>,
     },
+    /// ジェネリック関数の型 (例: `<T>(x: T) => T`)
+    ///
+    /// `func` は関数型で、その中の `Type::Var` が型引数を参照する
+    Generic {
+        type_params: Vec<TypeParam>,
+        func: Box<Type>,
+    },
+    /// 型引数の参照 (例: `<T>(x: T) => T` の `T`)
+    ///
+    /// `id` は宣言した `TypeParam` の `id` に等しい。
+    /// 型推論 (`infer`) では、単一化で型を決める型変数も表す
+    Var {
+        name: String,
+        id: usize,
+    },
+    /// オブジェクト型 (例: `{ a: number; b: boolean }`)
+    Object {
+        props: Vec<Prop>,
+    },
+    /// 合併型 (例: `number | boolean`)
+    ///
+    /// `Type::union` で作り、入れ子の合併型や重複した型を含まないようにする
+    Union(Vec<Type>),
+    /// 型注釈に書かれた型エイリアスや型引数の名前 (例: `Point`, `T`)
+    ///
+    /// 型検査の際に、その位置から見える宣言の `Type::Alias` や `Type::Var` に置き換える
+    Ref(String),
+    /// 型エイリアス
+    ///
+    /// `id` は宣言ごとに異なり、同じ名前の別の宣言と区別する。
+    /// `name` は表示する名前で、同じ名前の型が見えている宣言では番号を付ける (例: `T1`)。
+    /// オブジェクト型や関数型の中であれば自分自身を参照してもよい (例: `type List = { tail: List } | undefined`)
+    Alias {
+        name: String,
+        id: usize,
+    },
+    /// 構文エラーなどで型が決まらない部分の型 (どの型とも矛盾しないものとして扱う)
+    Unknown,
 }
 
-#[derive(Debug, PartialEq)]
+impl Type {
+    /// 与えられた型の合併型を作る (1 つしかなければその型自身になる)
+    pub fn union(types: impl IntoIterator<Item = Type>) -> Type {
+        let mut members: Vec<Type> = vec![];
+        for typ in types {
+            let typs = match typ {
+                Type::Union(typs) => typs,
+                typ => vec![typ],
+            };
+            for typ in typs {
+                if !members.contains(&typ) {
+                    members.push(typ);
+                }
+            }
+        }
+        if members.len() == 1 {
+            members.remove(0)
+        } else {
+            Type::Union(members)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Param {
-    name: String,
-    typ: Type,
+    pub name: String,
+    pub typ: Type,
+}
+
+/// 引数名は関数型の同一性に影響しない (例: `(x: number) => number` と `(y: number) => number` は同じ型)
+impl PartialEq for Param {
+    fn eq(&self, other: &Self) -> bool {
+        self.typ == other.typ
+    }
+}
+
+/// ジェネリック関数の型の型引数の宣言
+///
+/// `id` は宣言ごとに異なり、同じ名前の別の型引数と区別する (構文解析の時点では 0 で、型検査の際に振り直す)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeParam {
+    pub name: String,
+    pub id: usize,
+}
+
+/// オブジェクト型のプロパティ
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prop {
+    pub name: String,
+    pub typ: Type,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_in_scope(f, &mut vec![])
+    }
+}
+
+impl Type {
+    /// `scope` は外側のジェネリック関数の型引数の `id` と、それを表示する名前の対応
+    ///
+    /// 外側の型引数と同じ名前の別の型引数は、番号を付けた名前で表示して区別する
+    /// (例: `<T>(x: T) => <T1>(y: T1) => T`)
+    fn fmt_in_scope(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        scope: &mut Vec<(usize, String)>,
+    ) -> std::fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "boolean"),
+            Type::Number => write!(f, "number"),
+            Type::String => write!(f, "string"),
+            Type::Undefined => write!(f, "undefined"),
+            Type::Func { params, ret_type } => {
+                write!(f, "(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", param.name)?;
+                    param.typ.fmt_in_scope(f, scope)?;
+                }
+                write!(f, ") => ")?;
+                ret_type.fmt_in_scope(f, scope)
+            }
+            Type::Generic { type_params, func } => {
+                let outer = scope.len();
+                write!(f, "<")?;
+                for (i, type_param) in type_params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let mut name = type_param.name.clone();
+                    let mut n = 0;
+                    while scope
+                        .iter()
+                        .any(|(id, shown)| *shown == name && *id != type_param.id)
+                    {
+                        n += 1;
+                        name = format!("{}{}", type_param.name, n);
+                    }
+                    write!(f, "{}", name)?;
+                    scope.push((type_param.id, name));
+                }
+                write!(f, ">")?;
+                func.fmt_in_scope(f, scope)?;
+                scope.truncate(outer);
+                Ok(())
+            }
+            Type::Object { props } if props.is_empty() => write!(f, "{{}}"),
+            Type::Object { props } => {
+                write!(f, "{{ ")?;
+                for (i, prop) in props.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: ", prop.name)?;
+                    prop.typ.fmt_in_scope(f, scope)?;
+                }
+                write!(f, " }}")
+            }
+            Type::Union(types) => {
+                for (i, typ) in types.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    match typ {
+                        // `=>` の右側は合併型を含むため括弧で囲む
+                        Type::Func { .. } | Type::Generic { .. } => {
+                            write!(f, "(")?;
+                            typ.fmt_in_scope(f, scope)?;
+                            write!(f, ")")?;
+                        }
+                        _ => typ.fmt_in_scope(f, scope)?,
+                    }
+                }
+                Ok(())
+            }
+            Type::Var { name, id } => {
+                let shown = scope
+                    .iter()
+                    .rev()
+                    .find(|(scope_id, _)| scope_id == id)
+                    .map_or(name, |(_, shown)| shown);
+                write!(f, "{}", shown)
+            }
+            Type::Ref(name) | Type::Alias { name, .. } => write!(f, "{}", name),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
 }
@@ -0,0 +1,2771 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use crate::tiny_ts::basic::Term;
+use crate::tiny_ts::basic::resolve::{Resolve, TypeNames};
+use crate::tiny_ts::basic::term;
+use crate::tiny_ts::basic::typ::{Param, Prop, Type, TypeParam};
+use crate::tiny_ts::span::Span;
+
+/// 型環境
+#[derive(Clone, Default)]
+struct TypeEnv {
+    /// 変数名から型への対応
+    vars: HashMap<String, Type>,
+    /// 見えている型エイリアスと型引数
+    types: TypeNames,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TypeError {
+    /// 期待した型と異なる (例: `1 + true`, `((x: number) => x)(true)`)
+    TypeMismatch {
+        expected: Type,
+        actual: Type,
+        span: Span,
+    },
+    /// 条件式が boolean 型でない (例: `1 ? 2 : 3`)
+    NonBooleanCondition { actual: Type, span: Span },
+    /// 未定義の変数を参照した (例: `x`)
+    UnknownVariable { name: String, span: Span },
+    /// 関数呼び出しの引数の数が異なる (例: `((x: number) => x)()`)
+    ArityMismatch {
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+    /// 関数型以外を呼び出した (例: `const x = 1; x();`)
+    NotCallable { actual: Type, span: Span },
+    /// 型引数の数が異なる (例: `(<T>(x: T) => x)<number, boolean>`, `((x: number) => x)<number>`)
+    TypeArityMismatch {
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+    /// 存在しないプロパティを参照した (例: `{ a: 1 }.b`, `(1).a`)
+    UnknownProperty {
+        name: String,
+        actual: Type,
+        span: Span,
+    },
+    /// 型引数の推論で、引数から互いに部分型でない型が導かれた (例: `const f = <T>(x: T, y: T) => x; f(1, true);`)
+    ///
+    /// `first_span` は `first` を導いた引数の位置
+    TypeArgumentConflict {
+        name: String,
+        first: Box<Type>,
+        second: Box<Type>,
+        span: Span,
+        first_span: Span,
+    },
+    /// 引数から型引数を推論できない (例: `const f = <T>() => 1; f();`)
+    UninferredTypeArgument { name: String, span: Span },
+    /// 宣言されていない型の名前を参照した (例: `(p: Point) => 1`)
+    UnknownType { name: String, span: Span },
+    /// 型エイリアスがオブジェクト型や関数型を挟まずに自分自身を参照している (例: `type A = B; type B = A;`)
+    CircularTypeAlias { name: String, span: Span },
+    /// 関数の本体の型が戻り値の型注釈と異なる (例: `(): number => true`)
+    ReturnTypeMismatch {
+        expected: Type,
+        actual: Type,
+        span: Span,
+        annotation_span: Span,
+    },
+    /// 型注釈を省略した引数の型が文脈から決まらない (型推論をしない型検査のみ、例: `(x) => x`)
+    MissingParamType { name: String, span: Span },
+    /// 型推論で、型変数 `var` がそれ自身を含む型 `typ` に等しくなければならない (例: `(f) => f(f)`)
+    InfiniteType {
+        var: Box<Type>,
+        typ: Box<Type>,
+        span: Span,
+    },
+    /// 型推論で、プロパティを参照するオブジェクトの型が決まらない (例: `(o) => o.a`)
+    UninferredObject { name: String, span: Span },
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::TypeMismatch { span, .. }
+            | TypeError::NonBooleanCondition { span, .. }
+            | TypeError::UnknownVariable { span, .. }
+            | TypeError::ArityMismatch { span, .. }
+            | TypeError::NotCallable { span, .. }
+            | TypeError::TypeArityMismatch { span, .. }
+            | TypeError::TypeArgumentConflict { span, .. }
+            | TypeError::UninferredTypeArgument { span, .. }
+            | TypeError::UnknownProperty { span, .. }
+            | TypeError::UnknownType { span, .. }
+            | TypeError::CircularTypeAlias { span, .. }
+            | TypeError::ReturnTypeMismatch { span, .. }
+            | TypeError::MissingParamType { span, .. }
+            | TypeError::InfiniteType { span, .. }
+            | TypeError::UninferredObject { span, .. } => *span,
+        }
+    }
+
+    /// エラーに含まれる型 (型推論で、報告する前に型変数を置き換えるため)
+    pub(super) fn types_mut(&mut self) -> Vec<&mut Type> {
+        match self {
+            TypeError::TypeMismatch {
+                expected, actual, ..
+            }
+            | TypeError::ReturnTypeMismatch {
+                expected, actual, ..
+            } => vec![expected, actual],
+            TypeError::TypeArgumentConflict { first, second, .. } => vec![first, second],
+            TypeError::InfiniteType { var, typ, .. } => vec![var, typ],
+            TypeError::NonBooleanCondition { actual, .. }
+            | TypeError::NotCallable { actual, .. }
+            | TypeError::UnknownProperty { actual, .. } => vec![actual],
+            TypeError::UnknownVariable { .. }
+            | TypeError::ArityMismatch { .. }
+            | TypeError::TypeArityMismatch { .. }
+            | TypeError::UninferredTypeArgument { .. }
+            | TypeError::UnknownType { .. }
+            | TypeError::CircularTypeAlias { .. }
+            | TypeError::MissingParamType { .. }
+            | TypeError::UninferredObject { .. } => vec![],
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::TypeMismatch {
+                expected, actual, ..
+            } => {
+                write!(f, "{} expected, but got {}", expected, actual)
+            }
+            TypeError::NonBooleanCondition { actual, .. } => {
+                write!(f, "boolean expected, but got {}", actual)
+            }
+            TypeError::UnknownVariable { name, .. } => write!(f, "unknown variable: {}", name),
+            TypeError::ArityMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "wrong number of arguments: expected {}, but got {}",
+                expected, actual
+            ),
+            TypeError::NotCallable { actual, .. } => {
+                write!(f, "function type expected, but got {}", actual)
+            }
+            TypeError::TypeArityMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "wrong number of type arguments: expected {}, but got {}",
+                expected, actual
+            ),
+            TypeError::UnknownProperty { name, actual, .. } => {
+                write!(f, "property {} does not exist on type {}", name, actual)
+            }
+            TypeError::TypeArgumentConflict {
+                name,
+                first,
+                second,
+                ..
+            } => write!(
+                f,
+                "type argument {} has conflicting candidates {} and {}",
+                name, first, second
+            ),
+            TypeError::UninferredTypeArgument { name, .. } => {
+                write!(
+                    f,
+                    "type argument {} cannot be inferred from the arguments",
+                    name
+                )
+            }
+            TypeError::UnknownType { name, .. } => write!(f, "unknown type: {}", name),
+            TypeError::CircularTypeAlias { name, .. } => {
+                write!(f, "type alias {} circularly references itself", name)
+            }
+            TypeError::ReturnTypeMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "return type {} expected, but the body has type {}",
+                expected, actual
+            ),
+            TypeError::MissingParamType { name, .. } => {
+                write!(f, "parameter {} needs a type annotation", name)
+            }
+            TypeError::InfiniteType { var, typ, .. } => {
+                write!(f, "could not infer a type: {} occurs in {}", var, typ)
+            }
+            TypeError::UninferredObject { name, .. } => write!(
+                f,
+                "could not infer the type of the object with property {}",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+pub fn typecheck(t: &Term) -> Result<Type, TypeError> {
+    let (typ, errors) = typecheck_all(t);
+    match errors.into_iter().next() {
+        None => Ok(typ),
+        Some(e) => Err(e),
+    }
+}
+
+/// 最初のエラーで止まらずに型検査を続け、推定できた型と全ての型エラーを返す
+///
+/// エラーのあった部分式は `Type::Unknown` として扱い、そこから波及するエラーは報告しない
+pub fn typecheck_all(t: &Term) -> (Type, Vec<TypeError>) {
+    let mut checker = Checker {
+        errors: vec![],
+        aliases: vec![],
+        type_param_count: Cell::new(0),
+    };
+    let mut env = TypeEnv::default();
+    // `undefined` は予約語ではなく、大域変数として定義されている
+    env.vars.insert("undefined".to_owned(), Type::Undefined);
+    let typ = checker.synth(t, &env);
+    (typ, checker.errors)
+}
+
+struct Checker {
+    errors: Vec<TypeError>,
+    /// 型エイリアスが別名を付けた型 (`Type::Alias` の `id` 番目)
+    aliases: Vec<Type>,
+    /// これまでに宣言した型引数の数 (次に宣言する型引数の `id`)
+    type_param_count: Cell<usize>,
+}
+
+impl Checker {
+    /// `t` の型を求める (合成モード)
+    fn synth(&mut self, t: &Term, env: &TypeEnv) -> Type {
+        match t {
+            Term::False(_) | Term::True(_) => Type::Boolean,
+            Term::Number(_, _) => Type::Number,
+            Term::String(_, _) => Type::String,
+            Term::Add { left, right, .. } => {
+                self.check(left, &Type::Number, env);
+                self.check(right, &Type::Number, env);
+                Type::Number
+            }
+            Term::StrictEq { left, right, .. } => {
+                self.synth(left, env);
+                self.synth(right, env);
+                Type::Boolean
+            }
+            Term::TypeOf { expr, .. } => {
+                self.synth(expr, env);
+                Type::String
+            }
+            Term::If { cond, thn, els, .. } => {
+                let (thn_env, els_env) = self.check_cond(cond, env);
+                let thn_type = self.synth(thn, thn_env.as_ref().unwrap_or(env));
+                let els_type = self.synth(els, els_env.as_ref().unwrap_or(env));
+                self.join(&thn_type, &els_type)
+            }
+            Term::Var { name, span } => match env.vars.get(name) {
+                Some(typ) => typ.clone(),
+                None => {
+                    self.errors.push(TypeError::UnknownVariable {
+                        name: name.clone(),
+                        span: *span,
+                    });
+                    Type::Unknown
+                }
+            },
+            Term::Func { .. } => self.synth_func(t, &[], None, env),
+            Term::Call { func, args, span } => {
+                let func_type = self.synth(func, env);
+                // ジェネリック関数の場合は、型引数の推論のために先に引数の型を求める
+                let (params, ret_type, arg_types) = match self.expand(&func_type) {
+                    Type::Func { params, ret_type } => {
+                        self.check_arity(&params, args, *span);
+                        (params, ret_type, None)
+                    }
+                    Type::Generic {
+                        type_params,
+                        func: generic,
+                    } => {
+                        // 型引数を推論できず型検査を打ち切る場合も、引数の数の誤りは報告する
+                        if let Type::Func { params, .. } = generic.as_ref() {
+                            self.check_arity(params, args, *span);
+                        }
+                        let (func, arg_types) =
+                            self.infer_type_args(&type_params, &generic, args, env, *span);
+                        match func {
+                            Type::Func { params, ret_type } => (params, ret_type, Some(arg_types)),
+                            _ => return Type::Unknown,
+                        }
+                    }
+                    actual => {
+                        if actual != Type::Unknown {
+                            self.errors.push(TypeError::NotCallable {
+                                actual: func_type,
+                                span: func.span(),
+                            });
+                        }
+                        for arg in args {
+                            self.synth(arg, env);
+                        }
+                        return Type::Unknown;
+                    }
+                };
+                match arg_types {
+                    None => {
+                        for (i, arg) in args.iter().enumerate() {
+                            match params.get(i) {
+                                Some(param) => self.check(arg, &param.typ, env),
+                                None => {
+                                    self.synth(arg, env);
+                                }
+                            }
+                        }
+                    }
+                    Some(arg_types) => {
+                        for ((arg, arg_type), param) in args.iter().zip(arg_types).zip(&params) {
+                            if !self.is_subtype(&arg_type, &param.typ) {
+                                self.errors.push(TypeError::TypeMismatch {
+                                    expected: param.typ.clone(),
+                                    actual: arg_type,
+                                    span: arg.span(),
+                                });
+                            }
+                        }
+                    }
+                }
+                *ret_type
+            }
+            Term::Instantiate {
+                func,
+                type_args,
+                span,
+            } => {
+                let func_type = self.synth(func, env);
+                let type_args = type_args
+                    .iter()
+                    .map(|(typ, span)| self.resolve(typ, *span, &env.types))
+                    .collect::<Vec<_>>();
+                match self.expand(&func_type) {
+                    Type::Generic { type_params, func } if type_params.len() == type_args.len() => {
+                        let map = type_params
+                            .iter()
+                            .map(|type_param| type_param.id)
+                            .zip(type_args)
+                            .collect::<Vec<_>>();
+                        self.subst(&func, &map)
+                    }
+                    Type::Unknown => Type::Unknown,
+                    actual => {
+                        let expected = match actual {
+                            Type::Generic { type_params, .. } => type_params.len(),
+                            _ => 0,
+                        };
+                        self.errors.push(TypeError::TypeArityMismatch {
+                            expected,
+                            actual: type_args.len(),
+                            span: *span,
+                        });
+                        Type::Unknown
+                    }
+                }
+            }
+            Term::Object { props, .. } => {
+                let mut prop_types: Vec<Prop> = vec![];
+                for (name, term) in props {
+                    let typ = self.synth(term, env);
+                    // 同じ名前のプロパティは後に書いたものが優先される
+                    match prop_types.iter_mut().find(|prop| &prop.name == name) {
+                        Some(prop) => prop.typ = typ,
+                        None => prop_types.push(Prop {
+                            name: name.clone(),
+                            typ,
+                        }),
+                    }
+                }
+                Type::Object { props: prop_types }
+            }
+            Term::Get { obj, name, span } => {
+                let obj_type = self.synth(obj, env);
+                match self.prop_type(&obj_type, name) {
+                    Some(typ) => typ,
+                    None => {
+                        self.errors.push(TypeError::UnknownProperty {
+                            name: name.clone(),
+                            actual: obj_type,
+                            span: *span,
+                        });
+                        Type::Unknown
+                    }
+                }
+            }
+            Term::Seq { body, rest, .. } => {
+                self.synth(body, env);
+                self.synth(rest, env)
+            }
+            Term::Const {
+                name, init, rest, ..
+            } => {
+                let typ = self.synth(init, env);
+                let mut new_env = env.clone();
+                new_env.vars.insert(name.clone(), typ);
+                self.synth(rest, &new_env)
+            }
+            Term::RecFunc { funcs, rest, .. } => {
+                // 本体を検査する前に全ての関数を環境に加え、互いに参照できるようにする
+                let mut new_env = env.clone();
+                let mut signatures = vec![];
+                for func in funcs {
+                    let (params, ret_type) = self.resolve_signature(func, &env.types);
+                    new_env.vars.insert(
+                        func.name.clone(),
+                        Type::Func {
+                            params: params.clone(),
+                            ret_type: Box::new(ret_type.clone()),
+                        },
+                    );
+                    signatures.push((params, ret_type));
+                }
+                for (func, (params, ret_type)) in funcs.iter().zip(signatures) {
+                    let mut func_env = new_env.clone();
+                    for param in params {
+                        func_env.vars.insert(param.name, param.typ);
+                    }
+                    let body_type = self.synth(&func.body, &func_env);
+                    self.check_return_type(
+                        body_type,
+                        &ret_type,
+                        last_statement(&func.body).span(),
+                        func.ret_type.1,
+                    );
+                }
+                self.synth(rest, &new_env)
+            }
+            Term::TypeDecls { decls, rest, .. } => {
+                let mut new_env = env.clone();
+                self.declare_type_aliases(decls, &mut new_env.types);
+                self.synth(rest, &new_env)
+            }
+            Term::Error { .. } => Type::Unknown,
+        }
+    }
+
+    /// 関数の本体の型が戻り値の型注釈に合っていることを確かめる
+    fn check_return_type(
+        &mut self,
+        body_type: Type,
+        ret_type: &Type,
+        span: Span,
+        annotation_span: Span,
+    ) {
+        if !self.is_subtype(&body_type, ret_type) {
+            self.errors.push(TypeError::ReturnTypeMismatch {
+                expected: ret_type.clone(),
+                actual: body_type,
+                span,
+                annotation_span,
+            });
+        }
+    }
+
+    /// `t` が `expected` 型の値であることを確かめる (検査モード)
+    ///
+    /// 無名関数や条件演算子、オブジェクトリテラルは期待される型を部分式に伝え、
+    /// 型注釈のない引数の型を文脈から決めたり、食い違った部分式でエラーを報告したりする。
+    /// それ以外の式は型を求めてから部分型であることを確かめる
+    fn check(&mut self, t: &Term, expected: &Type, env: &TypeEnv) {
+        let actual = match (t, self.expand(expected)) {
+            (
+                Term::Func { params, .. },
+                Type::Func {
+                    params: context,
+                    ret_type,
+                },
+            ) if params.len() == context.len() => {
+                let context = context
+                    .into_iter()
+                    .map(|param| Some(param.typ))
+                    .collect::<Vec<_>>();
+                self.synth_func(t, &context, Some(&ret_type), env)
+            }
+            // 期待される型が決まらない場合は、型注釈のない引数についてのエラーを重ねて報告しない
+            (Term::Func { params, .. }, Type::Unknown) => {
+                let context = vec![Some(Type::Unknown); params.len()];
+                self.synth_func(t, &context, None, env);
+                return;
+            }
+            (Term::If { cond, thn, els, .. }, _) => {
+                let (thn_env, els_env) = self.check_cond(cond, env);
+                self.check(thn, expected, thn_env.as_ref().unwrap_or(env));
+                self.check(els, expected, els_env.as_ref().unwrap_or(env));
+                return;
+            }
+            (Term::Object { props, .. }, Type::Object { props: context }) => {
+                let mut prop_types: Vec<Prop> = vec![];
+                for (name, term) in props {
+                    let typ = match context.iter().find(|prop| &prop.name == name) {
+                        Some(prop) => {
+                            self.check(term, &prop.typ, env);
+                            prop.typ.clone()
+                        }
+                        None => self.synth(term, env),
+                    };
+                    match prop_types.iter_mut().find(|prop| &prop.name == name) {
+                        Some(prop) => prop.typ = typ,
+                        None => prop_types.push(Prop {
+                            name: name.clone(),
+                            typ,
+                        }),
+                    }
+                }
+                Type::Object { props: prop_types }
+            }
+            _ => self.synth(t, env),
+        };
+        if !self.is_subtype(&actual, expected) {
+            self.errors.push(TypeError::TypeMismatch {
+                expected: expected.clone(),
+                actual,
+                span: t.span(),
+            });
+        }
+    }
+
+    /// 条件演算子の条件式を検査し、`then` と `else` の分岐の中で使う型環境を返す
+    ///
+    /// `typeof x === "number" ? ... : ...` の分岐の中では `x` の型を絞り込む (絞り込まない場合は `None`)
+    fn check_cond(&mut self, cond: &Term, env: &TypeEnv) -> (Option<TypeEnv>, Option<TypeEnv>) {
+        let cond_type = self.synth(cond, env);
+        if !self.is_subtype(&cond_type, &Type::Boolean) {
+            self.errors.push(TypeError::NonBooleanCondition {
+                actual: cond_type,
+                span: cond.span(),
+            });
+        }
+        match typeof_guard(cond) {
+            Some((name, tag)) if env.vars.contains_key(name) => {
+                let typ = &env.vars[name];
+                let mut thn_env = env.clone();
+                thn_env
+                    .vars
+                    .insert(name.to_owned(), self.narrow(typ, tag, true));
+                let mut els_env = env.clone();
+                els_env
+                    .vars
+                    .insert(name.to_owned(), self.narrow(typ, tag, false));
+                (Some(thn_env), Some(els_env))
+            }
+            _ => (None, None),
+        }
+    }
+
+    /// 無名関数 `t` の型を求める
+    ///
+    /// 型注釈のない引数の型は、文脈から期待される引数の型 `context` の同じ位置にあるものとする
+    /// (`None` や範囲外であれば型注釈が必要というエラーになる)。
+    /// 戻り値の型注釈がなく、文脈から期待される戻り値の型 `ret_context` があれば、本体をその型として検査する
+    fn synth_func(
+        &mut self,
+        t: &Term,
+        context: &[Option<Type>],
+        ret_context: Option<&Type>,
+        env: &TypeEnv,
+    ) -> Type {
+        let Term::Func {
+            type_params,
+            params,
+            ret_type,
+            body,
+            ..
+        } = t
+        else {
+            return self.synth(t, env);
+        };
+        let mut new_env = env.clone();
+        let type_params = type_params
+            .iter()
+            .map(|type_param| self.bind_type_param(&type_param.name, &mut new_env.types))
+            .collect::<Vec<_>>();
+        let params = params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| match (&param.typ, context.get(i)) {
+                (None, Some(Some(typ))) => Param {
+                    name: param.name.clone(),
+                    typ: typ.clone(),
+                },
+                _ => self.resolve_param(param, &new_env.types),
+            })
+            .collect::<Vec<_>>();
+        for param in &params {
+            new_env.vars.insert(param.name.clone(), param.typ.clone());
+        }
+        let ret_type = match (ret_type, ret_context) {
+            (Some((ret_type, annotation_span)), _) => {
+                let ret_type = self.resolve(ret_type, *annotation_span, &new_env.types);
+                let body_type = self.synth(body, &new_env);
+                self.check_return_type(body_type, &ret_type, body.span(), *annotation_span);
+                ret_type
+            }
+            (None, Some(ret_type)) => {
+                self.check(body, ret_type, &new_env);
+                ret_type.clone()
+            }
+            (None, None) => self.synth(body, &new_env),
+        };
+        let func = Type::Func {
+            params,
+            ret_type: Box::new(ret_type),
+        };
+        if type_params.is_empty() {
+            func
+        } else {
+            Type::Generic {
+                type_params,
+                func: Box::new(func),
+            }
+        }
+    }
+
+    /// 新しい型引数を宣言し、`types` から名前で参照できるようにする
+    fn bind_type_param(&self, name: &str, types: &mut TypeNames) -> TypeParam {
+        let type_param = self.fresh_type_param(name);
+        types.insert(
+            name.to_owned(),
+            Type::Var {
+                name: name.to_owned(),
+                id: type_param.id,
+            },
+        );
+        type_param
+    }
+
+    /// 他のどの型引数とも異なる `id` の型引数
+    fn fresh_type_param(&self, name: &str) -> TypeParam {
+        let id = self.type_param_count.get();
+        self.type_param_count.set(id + 1);
+        TypeParam {
+            name: name.to_owned(),
+            id,
+        }
+    }
+
+    /// 関数呼び出しの引数の数が関数型の引数の数と一致するか検査する
+    fn check_arity(&mut self, params: &[Param], args: &[Term], span: Span) {
+        if params.len() != args.len() {
+            self.errors.push(TypeError::ArityMismatch {
+                expected: params.len(),
+                actual: args.len(),
+                span,
+            });
+        }
+    }
+
+    /// ジェネリック関数 `<type_params>func` の呼び出しで、引数の型から型引数を推論して具体化した関数型と、引数の型の並び
+    ///
+    /// 型引数ごとに引数から導かれた型の候補のうち最も大きいものを選ぶ。
+    /// 型注釈のない引数を持つ無名関数は、他の引数から推論した型引数で引数の型を決めてから候補を集める
+    /// (例: `apply((x) => x + 1, 2)` の `x` は `2` から `number` とする)。
+    /// 関数型の引数に渡したジェネリック関数も、他の引数から推論した型引数で具体化してから候補を集める
+    /// (例: `apply(<V>(y: V) => y, 2)` の `V` は `2` から `number` とする)。
+    /// 候補が互いに部分型でない場合や候補がない場合はエラーを報告し、`Type::Unknown` を返す
+    fn infer_type_args(
+        &mut self,
+        type_params: &[TypeParam],
+        func: &Type,
+        args: &[Term],
+        env: &TypeEnv,
+        span: Span,
+    ) -> (Type, Vec<Type>) {
+        let Type::Func { params, .. } = func else {
+            let arg_types = args.iter().map(|arg| self.synth(arg, env)).collect();
+            return (Type::Unknown, arg_types);
+        };
+        let mut candidates: Vec<Option<(Type, Span)>> = vec![None; type_params.len()];
+        let mut has_conflict = false;
+        let mut arg_types = vec![None; args.len()];
+        let mut generic_args = vec![None; args.len()];
+        for (i, arg) in args.iter().enumerate() {
+            if is_context_sensitive(arg) {
+                continue;
+            }
+            let arg_type = self.synth(arg, env);
+            match params.get(i) {
+                Some(_) if matches!(arg_type, Type::Generic { .. }) => {
+                    generic_args[i] = Some(arg_type);
+                    continue;
+                }
+                Some(param) => {
+                    has_conflict |= self.add_candidates(
+                        &param.typ,
+                        &arg_type,
+                        arg,
+                        type_params,
+                        &mut candidates,
+                    );
+                }
+                None => {}
+            }
+            arg_types[i] = Some(arg_type);
+        }
+        for (i, arg) in args.iter().enumerate() {
+            if arg_types[i].is_some() {
+                continue;
+            }
+            let arg_type = match params.get(i) {
+                Some(param) => {
+                    // まだ候補のない型引数を含む引数の型は、文脈として使わない
+                    let map = type_params
+                        .iter()
+                        .zip(&candidates)
+                        .filter_map(|(type_param, candidate)| {
+                            candidate
+                                .as_ref()
+                                .map(|(typ, _)| (type_param.id, typ.clone()))
+                        })
+                        .collect::<Vec<_>>();
+                    let context = match self.expand(&self.subst(&param.typ, &map)) {
+                        Type::Func { params, .. } => params
+                            .into_iter()
+                            .map(|param| {
+                                let uninferred = type_params
+                                    .iter()
+                                    .any(|type_param| occurs(type_param.id, &param.typ));
+                                (!uninferred).then_some(param.typ)
+                            })
+                            .collect(),
+                        _ => vec![],
+                    };
+                    let arg_type = match generic_args[i].take() {
+                        Some(generic) => self.instantiate_arg(generic, &context),
+                        None => self.synth_func(arg, &context, None, env),
+                    };
+                    has_conflict |= self.add_candidates(
+                        &param.typ,
+                        &arg_type,
+                        arg,
+                        type_params,
+                        &mut candidates,
+                    );
+                    arg_type
+                }
+                None => self.synth(arg, env),
+            };
+            arg_types[i] = Some(arg_type);
+        }
+        let arg_types = arg_types.into_iter().flatten().collect();
+        let mut map = vec![];
+        for (type_param, candidate) in type_params.iter().zip(candidates) {
+            match candidate {
+                Some((typ, _)) => map.push((type_param.id, typ)),
+                None => {
+                    // 引数が足りないために推論できない場合は、引数の数の誤りだけを報告する
+                    if params.len() <= args.len() {
+                        self.errors.push(TypeError::UninferredTypeArgument {
+                            name: type_param.name.clone(),
+                            span,
+                        });
+                    }
+                    has_conflict = true;
+                }
+            }
+        }
+        if has_conflict {
+            return (Type::Unknown, arg_types);
+        }
+        (self.subst(func, &map), arg_types)
+    }
+
+    /// 関数型の引数に渡したジェネリック関数の型 `arg_type` を、期待される引数の型 `context` から型引数を推論して具体化する
+    ///
+    /// 型引数の候補が複数あればそれらを合わせた型とする。推論できない型引数があれば `arg_type` をそのまま返す
+    fn instantiate_arg(&self, arg_type: Type, context: &[Option<Type>]) -> Type {
+        let Type::Generic { type_params, func } = &arg_type else {
+            return arg_type;
+        };
+        let Type::Func { params, .. } = func.as_ref() else {
+            return arg_type;
+        };
+        let mut found = vec![];
+        for (param, expected) in params.iter().zip(context) {
+            if let Some(expected) = expected {
+                self.collect_candidates(&param.typ, expected, type_params, &mut found);
+            }
+        }
+        let mut map = vec![];
+        for (i, type_param) in type_params.iter().enumerate() {
+            let candidate = found
+                .iter()
+                .filter(|(j, _)| *j == i)
+                .map(|(_, typ)| typ.clone())
+                .reduce(|t1, t2| self.join(&t1, &t2));
+            match candidate {
+                Some(typ) => map.push((type_param.id, typ)),
+                None => return arg_type,
+            }
+        }
+        self.subst(func, &map)
+    }
+
+    /// 引数 `arg` の型 `arg_type` から導かれる型引数の候補を `candidates` に加え、候補が食い違えばエラーを報告して `true` を返す
+    fn add_candidates(
+        &mut self,
+        param: &Type,
+        arg_type: &Type,
+        arg: &Term,
+        type_params: &[TypeParam],
+        candidates: &mut [Option<(Type, Span)>],
+    ) -> bool {
+        let mut found = vec![];
+        self.collect_candidates(param, arg_type, type_params, &mut found);
+        let mut has_conflict = false;
+        for (i, typ) in found {
+            match &candidates[i] {
+                None => candidates[i] = Some((typ, arg.span())),
+                Some((candidate, _)) if self.is_subtype(&typ, candidate) => {}
+                Some((candidate, _)) if self.is_subtype(candidate, &typ) => {
+                    candidates[i] = Some((typ, arg.span()));
+                }
+                Some((candidate, first_span)) => {
+                    self.errors.push(TypeError::TypeArgumentConflict {
+                        name: type_params[i].name.clone(),
+                        first: Box::new(candidate.clone()),
+                        second: Box::new(typ),
+                        span: arg.span(),
+                        first_span: *first_span,
+                    });
+                    has_conflict = true;
+                }
+            }
+        }
+        has_conflict
+    }
+
+    /// 引数の型 `param` と実引数の型 `arg` の構造を照らし合わせ、`type_params` の型引数 (の添字) に当てはまる型を集める
+    fn collect_candidates(
+        &self,
+        param: &Type,
+        arg: &Type,
+        type_params: &[TypeParam],
+        found: &mut Vec<(usize, Type)>,
+    ) {
+        match (param, arg) {
+            (Type::Var { id, .. }, _) => {
+                if let Some(i) = type_params.iter().position(|param| param.id == *id) {
+                    found.push((i, arg.clone()));
+                }
+            }
+            // 型引数を含まない型に当てはまらない部分から推論する
+            // (例: `T | undefined` と `number | undefined` からは `T` に `number` が当てはまる)
+            (Type::Union(types), _) => {
+                let (generic, fixed): (Vec<_>, Vec<_>) = types.iter().partition(|typ| {
+                    type_params
+                        .iter()
+                        .any(|type_param| occurs(type_param.id, typ))
+                });
+                if let [generic] = generic[..] {
+                    let rest = self
+                        .members(arg)
+                        .into_iter()
+                        .filter(|typ| !fixed.iter().any(|fixed| self.is_subtype(typ, fixed)))
+                        .collect::<Vec<_>>();
+                    if !rest.is_empty() {
+                        self.collect_candidates(generic, &Type::union(rest), type_params, found);
+                    }
+                }
+            }
+            (_, Type::Alias { .. }) => {
+                self.collect_candidates(param, &self.expand(arg), type_params, found)
+            }
+            (
+                Type::Func {
+                    params: params1,
+                    ret_type: ret_type1,
+                },
+                Type::Func {
+                    params: params2,
+                    ret_type: ret_type2,
+                },
+            ) if params1.len() == params2.len() => {
+                for (p1, p2) in params1.iter().zip(params2) {
+                    self.collect_candidates(&p1.typ, &p2.typ, type_params, found);
+                }
+                self.collect_candidates(ret_type1, ret_type2, type_params, found);
+            }
+            (Type::Object { props: props1 }, Type::Object { props: props2 }) => {
+                for p1 in props1 {
+                    if let Some(p2) = props2.iter().find(|p2| p2.name == p1.name) {
+                        self.collect_candidates(&p1.typ, &p2.typ, type_params, found);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `typ` の中の型引数を `map` に従って置き換える (`map` は型引数の `id` と置き換える型の組)
+    fn subst(&self, typ: &Type, map: &[(usize, Type)]) -> Type {
+        subst(typ, map, &mut |name| self.fresh_type_param(name))
+    }
+
+    /// 型エイリアスであれば、別名を付けた型まで展開する
+    ///
+    /// 再帰的な型エイリアスはオブジェクト型や関数型の中で自分自身を参照するため、展開するのは最も外側だけである
+    fn expand(&self, typ: &Type) -> Type {
+        let mut typ = typ;
+        while let Type::Alias { id, .. } = typ {
+            typ = &self.aliases[*id];
+        }
+        typ.clone()
+    }
+
+    /// 型エイリアスを展開し、合併型を構成する型の並びにする
+    fn members(&self, typ: &Type) -> Vec<Type> {
+        match self.expand(typ) {
+            Type::Union(types) => types.iter().flat_map(|typ| self.members(typ)).collect(),
+            typ => vec![typ],
+        }
+    }
+
+    /// `t1` が `t2` の部分型であるか
+    ///
+    /// - オブジェクト型は `t2` のプロパティを全て持ち、それぞれの型が部分型であればよい (幅と深さの部分型)
+    /// - 関数型は引数の数が等しく、引数の型が反変、戻り値の型が共変であればよい
+    /// - ジェネリック関数の型は型引数の数が等しく、型引数を揃えたときに関数型が部分型であればよい
+    /// - `Type::Unknown` はどの型の部分型でも上位型でもあるものとみなす
+    /// - 型エイリアスは展開して比べる (再帰的な型は無限に展開したときの構造で比べる)
+    fn is_subtype(&self, t1: &Type, t2: &Type) -> bool {
+        self.is_subtype_with(t1, t2, &mut vec![])
+    }
+
+    /// `assumed` は展開している途中の型エイリアスの組で、同じ組が再び現れたら部分型であると仮定する
+    ///
+    /// 再帰的な型の部分型関係を余帰納的に判定するため、矛盾が見つからなければ部分型とする
+    fn is_subtype_with(&self, t1: &Type, t2: &Type, assumed: &mut Vec<(Type, Type)>) -> bool {
+        match (t1, t2) {
+            _ if t1 == t2 => true,
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Alias { .. }, _) | (_, Type::Alias { .. }) => {
+                let pair = (t1.clone(), t2.clone());
+                if assumed.contains(&pair) {
+                    return true;
+                }
+                assumed.push(pair);
+                let result = self.is_subtype_with(&self.expand(t1), &self.expand(t2), assumed);
+                assumed.pop();
+                result
+            }
+            (Type::Union(types), _) => types
+                .iter()
+                .all(|typ| self.is_subtype_with(typ, t2, assumed)),
+            (_, Type::Union(types)) => types
+                .iter()
+                .any(|typ| self.is_subtype_with(t1, typ, assumed)),
+            (
+                Type::Func {
+                    params: params1,
+                    ret_type: ret_type1,
+                },
+                Type::Func {
+                    params: params2,
+                    ret_type: ret_type2,
+                },
+            ) => {
+                params1.len() == params2.len()
+                    && params1
+                        .iter()
+                        .zip(params2)
+                        .all(|(p1, p2)| self.is_subtype_with(&p2.typ, &p1.typ, assumed))
+                    && self.is_subtype_with(ret_type1, ret_type2, assumed)
+            }
+            (
+                Type::Generic {
+                    type_params: type_params1,
+                    func: func1,
+                },
+                Type::Generic {
+                    type_params: type_params2,
+                    func: func2,
+                },
+            ) if type_params1.len() == type_params2.len() => {
+                let map = type_params2
+                    .iter()
+                    .zip(type_params1)
+                    .map(|(p2, p1)| {
+                        let var = Type::Var {
+                            name: p1.name.clone(),
+                            id: p1.id,
+                        };
+                        (p2.id, var)
+                    })
+                    .collect::<Vec<_>>();
+                self.is_subtype_with(func1, &self.subst(func2, &map), assumed)
+            }
+            (Type::Object { props: props1 }, Type::Object { props: props2 }) => {
+                props2.iter().all(|p2| {
+                    props1.iter().any(|p1| {
+                        p1.name == p2.name && self.is_subtype_with(&p1.typ, &p2.typ, assumed)
+                    })
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// `t1` と `t2` の共通の上位型 (条件演算子の型)
+    ///
+    /// 一方が他方の部分型であればその上位型、オブジェクト型や関数型どうしであれば構造ごとの共通の上位型、
+    /// それ以外は合併型にする
+    fn join(&self, t1: &Type, t2: &Type) -> Type {
+        self.join_with(t1, t2, &mut vec![])
+    }
+
+    /// `visiting` は構造をたどっている途中の型の組
+    ///
+    /// 再帰的な型エイリアスを展開し続けないよう、同じ組が再び現れたら構造をたどらずに合併型にする
+    fn join_with(&self, t1: &Type, t2: &Type, visiting: &mut Vec<(Type, Type)>) -> Type {
+        match (t1, t2) {
+            (Type::Unknown, t) | (t, Type::Unknown) => t.clone(),
+            _ if self.is_subtype(t2, t1) => t1.clone(),
+            _ if self.is_subtype(t1, t2) => t2.clone(),
+            _ => {
+                let pair = (t1.clone(), t2.clone());
+                let joined = if visiting.contains(&pair) {
+                    None
+                } else {
+                    visiting.push(pair);
+                    let joined = self.join_structure(&self.expand(t1), &self.expand(t2), visiting);
+                    visiting.pop();
+                    joined
+                };
+                joined.unwrap_or_else(|| Type::union([t1.clone(), t2.clone()]))
+            }
+        }
+    }
+
+    fn join_structure(
+        &self,
+        t1: &Type,
+        t2: &Type,
+        visiting: &mut Vec<(Type, Type)>,
+    ) -> Option<Type> {
+        match (t1, t2) {
+            (
+                Type::Func {
+                    params: params1,
+                    ret_type: ret_type1,
+                },
+                Type::Func {
+                    params: params2,
+                    ret_type: ret_type2,
+                },
+            ) if params1.len() == params2.len() => {
+                let params = params1
+                    .iter()
+                    .zip(params2)
+                    .map(|(p1, p2)| {
+                        Some(Param {
+                            name: p1.name.clone(),
+                            typ: self.meet(&p1.typ, &p2.typ, visiting)?,
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+                Some(Type::Func {
+                    params,
+                    ret_type: Box::new(self.join_with(ret_type1, ret_type2, visiting)),
+                })
+            }
+            // 両方にあるプロパティだけを残す
+            (Type::Object { props: props1 }, Type::Object { props: props2 }) => {
+                let props = props1
+                    .iter()
+                    .filter_map(|p1| {
+                        let p2 = props2.iter().find(|p2| p2.name == p1.name)?;
+                        Some(Prop {
+                            name: p1.name.clone(),
+                            typ: self.join_with(&p1.typ, &p2.typ, visiting),
+                        })
+                    })
+                    .collect();
+                Some(Type::Object { props })
+            }
+            _ => None,
+        }
+    }
+
+    /// `t1` と `t2` の最大の共通の部分型 (関数型の引数の型を `join` するときに使う)
+    ///
+    /// 共通の部分型がない場合や、再帰的な型エイリアスの同じ組が再び現れた場合は `None` を返す
+    fn meet(&self, t1: &Type, t2: &Type, visiting: &mut Vec<(Type, Type)>) -> Option<Type> {
+        match (t1, t2) {
+            (Type::Unknown, t) | (t, Type::Unknown) => Some(t.clone()),
+            _ if self.is_subtype(t1, t2) => Some(t1.clone()),
+            _ if self.is_subtype(t2, t1) => Some(t2.clone()),
+            (Type::Alias { .. }, _) | (_, Type::Alias { .. }) => {
+                let pair = (t1.clone(), t2.clone());
+                if visiting.contains(&pair) {
+                    return None;
+                }
+                visiting.push(pair);
+                let met = self.meet(&self.expand(t1), &self.expand(t2), visiting);
+                visiting.pop();
+                met
+            }
+            (Type::Union(types), t) | (t, Type::Union(types)) => {
+                let types = types
+                    .iter()
+                    .filter_map(|typ| self.meet(typ, t, visiting))
+                    .collect::<Vec<_>>();
+                if types.is_empty() {
+                    None
+                } else {
+                    Some(Type::union(types))
+                }
+            }
+            (
+                Type::Func {
+                    params: params1,
+                    ret_type: ret_type1,
+                },
+                Type::Func {
+                    params: params2,
+                    ret_type: ret_type2,
+                },
+            ) if params1.len() == params2.len() => {
+                let params = params1
+                    .iter()
+                    .zip(params2)
+                    .map(|(p1, p2)| Param {
+                        name: p1.name.clone(),
+                        typ: self.join_with(&p1.typ, &p2.typ, visiting),
+                    })
+                    .collect();
+                let ret_type = self.meet(ret_type1, ret_type2, visiting)?;
+                Some(Type::Func {
+                    params,
+                    ret_type: Box::new(ret_type),
+                })
+            }
+            // どちらかにあるプロパティを全て持つ
+            (Type::Object { props: props1 }, Type::Object { props: props2 }) => {
+                let mut props = vec![];
+                for p1 in props1 {
+                    let typ = match props2.iter().find(|p2| p2.name == p1.name) {
+                        Some(p2) => self.meet(&p1.typ, &p2.typ, visiting)?,
+                        None => p1.typ.clone(),
+                    };
+                    props.push(Prop {
+                        name: p1.name.clone(),
+                        typ,
+                    });
+                }
+                for p2 in props2 {
+                    if !props1.iter().any(|p1| p1.name == p2.name) {
+                        props.push(p2.clone());
+                    }
+                }
+                Some(Type::Object { props })
+            }
+            _ => None,
+        }
+    }
+
+    /// 型 `typ` の値のプロパティ `name` の型 (合併型の場合は全ての型にプロパティがなければならない)
+    fn prop_type(&self, typ: &Type, name: &str) -> Option<Type> {
+        match typ {
+            Type::Unknown => Some(Type::Unknown),
+            Type::Alias { .. } => self.prop_type(&self.expand(typ), name),
+            Type::Object { props } => props
+                .iter()
+                .find(|prop| prop.name == name)
+                .map(|prop| prop.typ.clone()),
+            Type::Union(types) => types
+                .iter()
+                .map(|typ| self.prop_type(typ, name))
+                .collect::<Option<Vec<_>>>()
+                .map(Type::union),
+            _ => None,
+        }
+    }
+
+    /// `typeof` の結果が `tag` に一致する (`matches` が `false` なら一致しない) 場合に絞り込んだ型
+    ///
+    /// 絞り込んでもどの型も除かれない場合や、どの型も残らない場合は元の型のままにする
+    fn narrow(&self, typ: &Type, tag: &str, matches: bool) -> Type {
+        let types = self.members(typ);
+        let count = types.len();
+        let narrowed = types
+            .into_iter()
+            .filter(|typ| typeof_tag(typ).is_none_or(|t| (t == tag) == matches))
+            .collect::<Vec<_>>();
+        if narrowed.is_empty() || narrowed.len() == count {
+            typ.clone()
+        } else {
+            Type::union(narrowed)
+        }
+    }
+}
+
+impl Resolve for Checker {
+    fn errors(&mut self) -> &mut Vec<TypeError> {
+        &mut self.errors
+    }
+
+    fn aliases(&mut self) -> &mut Vec<Type> {
+        &mut self.aliases
+    }
+
+    /// 型引数を新しく宣言し、ジェネリック関数の型のまま解決する
+    fn resolve_generic(
+        &mut self,
+        type_params: &[TypeParam],
+        func: &Type,
+        span: Span,
+        types: &TypeNames,
+    ) -> Type {
+        let mut new_types = types.clone();
+        let type_params = type_params
+            .iter()
+            .map(|type_param| self.bind_type_param(&type_param.name, &mut new_types))
+            .collect();
+        Type::Generic {
+            type_params,
+            func: Box::new(self.resolve(func, span, &new_types)),
+        }
+    }
+
+    /// 型推論をしないため、エラーとして報告して `Type::Unknown` とする
+    fn missing_param_type(&mut self, param: &term::Param) -> Type {
+        self.errors.push(TypeError::MissingParamType {
+            name: param.name.clone(),
+            span: param.span,
+        });
+        Type::Unknown
+    }
+}
+
+/// 文の並びの最後の文 (関数本体の値となる式)
+pub(super) fn last_statement(t: &Term) -> &Term {
+    match t {
+        Term::Seq { rest, .. }
+        | Term::Const { rest, .. }
+        | Term::RecFunc { rest, .. }
+        | Term::TypeDecls { rest, .. } => last_statement(rest),
+        _ => t,
+    }
+}
+
+/// `typ` に型引数 `id` が (ジェネリック関数の型の中で宣言されたものとしてではなく) 現れるか
+pub(super) fn occurs(id: usize, typ: &Type) -> bool {
+    match typ {
+        Type::Var { id: other, .. } => *other == id,
+        Type::Func { params, ret_type } => {
+            params.iter().any(|param| occurs(id, &param.typ)) || occurs(id, ret_type)
+        }
+        Type::Generic { type_params, func } => {
+            type_params.iter().all(|param| param.id != id) && occurs(id, func)
+        }
+        Type::Object { props } => props.iter().any(|prop| occurs(id, &prop.typ)),
+        Type::Union(types) => types.iter().any(|typ| occurs(id, typ)),
+        Type::Boolean
+        | Type::Number
+        | Type::String
+        | Type::Undefined
+        | Type::Ref(_)
+        | Type::Alias { .. }
+        | Type::Unknown => false,
+    }
+}
+
+/// `typ` の中の型引数を `map` に従って置き換える (`map` は型引数の `id` と置き換える型の組)
+///
+/// ジェネリック関数の型の中では、置き換える型に現れる型引数を取り込んでしまわないよう、
+/// 同じ `id` の型引数の宣言を `fresh` で作った新しいものに付け替えてから置き換える
+pub(super) fn subst(
+    typ: &Type,
+    map: &[(usize, Type)],
+    fresh: &mut impl FnMut(&str) -> TypeParam,
+) -> Type {
+    match typ {
+        Type::Var { id, .. } => match map.iter().find(|(var, _)| var == id) {
+            Some((_, typ)) => typ.clone(),
+            None => typ.clone(),
+        },
+        Type::Func { params, ret_type } => Type::Func {
+            params: params
+                .iter()
+                .map(|param| Param {
+                    name: param.name.clone(),
+                    typ: subst(&param.typ, map, fresh),
+                })
+                .collect(),
+            ret_type: Box::new(subst(ret_type, map, fresh)),
+        },
+        Type::Generic { type_params, func } => {
+            // 宣言された型引数は外側の同じ `id` の型引数とは別のものである
+            let mut map = map
+                .iter()
+                .filter(|(var, _)| type_params.iter().all(|param| param.id != *var))
+                .cloned()
+                .collect::<Vec<_>>();
+            let type_params = type_params
+                .iter()
+                .map(|param| {
+                    if !map.iter().any(|(_, typ)| occurs(param.id, typ)) {
+                        return param.clone();
+                    }
+                    let fresh = fresh(&param.name);
+                    map.push((
+                        param.id,
+                        Type::Var {
+                            name: fresh.name.clone(),
+                            id: fresh.id,
+                        },
+                    ));
+                    fresh
+                })
+                .collect();
+            Type::Generic {
+                type_params,
+                func: Box::new(subst(func, &map, fresh)),
+            }
+        }
+        Type::Object { props } => Type::Object {
+            props: props
+                .iter()
+                .map(|prop| Prop {
+                    name: prop.name.clone(),
+                    typ: subst(&prop.typ, map, fresh),
+                })
+                .collect(),
+        },
+        Type::Union(types) => Type::union(types.iter().map(|typ| subst(typ, map, fresh))),
+        // 型エイリアスはジェネリック関数の中では宣言できないため、その定義は型引数を含まない
+        Type::Boolean
+        | Type::Number
+        | Type::String
+        | Type::Undefined
+        | Type::Ref(_)
+        | Type::Alias { .. }
+        | Type::Unknown => typ.clone(),
+    }
+}
+
+/// 型注釈のない引数を持つ無名関数であるか (文脈から引数の型を決める必要がある)
+fn is_context_sensitive(t: &Term) -> bool {
+    matches!(t, Term::Func { params, .. } if params.iter().any(|param| param.typ.is_none()))
+}
+
+/// 条件式が `typeof x === "..."` (または `"..." === typeof x`) であれば、変数名と比べる文字列を返す
+fn typeof_guard(cond: &Term) -> Option<(&str, &str)> {
+    let Term::StrictEq { left, right, .. } = cond else {
+        return None;
+    };
+    match (left.as_ref(), right.as_ref()) {
+        (Term::TypeOf { expr, .. }, Term::String(tag, _))
+        | (Term::String(tag, _), Term::TypeOf { expr, .. }) => match expr.as_ref() {
+            Term::Var { name, .. } => Some((name, tag)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `typeof` がその型の値に対して返す文字列 (`Type::Unknown` などの場合は決まらない)
+fn typeof_tag(typ: &Type) -> Option<&'static str> {
+    match typ {
+        Type::Boolean => Some("boolean"),
+        Type::Number => Some("number"),
+        Type::String => Some("string"),
+        Type::Undefined => Some("undefined"),
+        Type::Func { .. } | Type::Generic { .. } => Some("function"),
+        Type::Object { .. } => Some("object"),
+        Type::Union(_) | Type::Var { .. } | Type::Ref(_) | Type::Alias { .. } | Type::Unknown => {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tiny_ts::basic::{parse, parse_with_recovery};
+    use crate::tiny_ts::span::LineIndex;
+
+    const S: Span = Span::new(0, 0);
+
+    #[test]
+    fn test() {
+        let term = parse("false").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
+
+        let term = parse("true ? 0 : 1 + 2").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("(x: number, y: boolean) => y ? x : 1").unwrap();
+        assert_eq!(
+            typecheck(&term),
+            Ok(Type::Func {
+                params: vec![
+                    Param {
+                        name: "x".to_owned(),
+                        typ: Type::Number,
+                    },
+                    Param {
+                        name: "y".to_owned(),
+                        typ: Type::Boolean,
+                    },
+                ],
+                ret_type: Box::new(Type::Number),
+            })
+        );
+
+        let term = parse("const x = 1; const y = true; y ? x : 2;").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("const f = () => 1; f() + 2;").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("1; true;").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let term = parse("(x: number, y: boolean) => x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number, y: boolean) => number"
+        );
+
+        let term = parse("const f = (x: number) => x; f();").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "wrong number of arguments: expected 1, but got 0"
+        );
+
+        let term = parse("const x = 1; x();").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "function type expected, but got number"
+        );
+    }
+
+    // 関数型は引数名を区別しないこと
+
+    #[test]
+    fn test_func_type_ignores_param_names() {
+        let term =
+            parse("const f = (x: number) => x; const g = (y: number) => y; true ? f : g;").unwrap();
+        assert_eq!(
+            typecheck(&term),
+            Ok(Type::Func {
+                params: vec![Param {
+                    name: "z".to_owned(),
+                    typ: Type::Number,
+                }],
+                ret_type: Box::new(Type::Number),
+            })
+        );
+    }
+
+    // 定義された変数のみ参照できること
+
+    #[test]
+    fn test_unknown_variable_is_ng() {
+        assert_eq!(
+            typecheck(&parse("x").unwrap()),
+            Err(TypeError::UnknownVariable {
+                name: "x".to_owned(),
+                span: Span::new(0, 1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_param_out_of_scope_is_ng() {
+        assert_eq!(
+            typecheck(&parse("const f = (x: number) => x; x;").unwrap()),
+            Err(TypeError::UnknownVariable {
+                name: "x".to_owned(),
+                span: Span::new(28, 29),
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_param_type_is_ng() {
+        let err = typecheck(&parse("(x, y: number) => x + y").unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::MissingParamType {
+                name: "x".to_owned(),
+                span: Span::new(1, 2),
+            }
+        );
+        assert_eq!(err.to_string(), "parameter x needs a type annotation");
+        assert_eq!(err.span().range(), 1..2);
+
+        let source = "function f(x): number { return x; } f(true);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "parameter x needs a type annotation"
+        );
+    }
+
+    // 関数呼び出しの引数の数と型が一致すること
+
+    #[test]
+    fn test_call_with_too_few_args_is_ng() {
+        assert_eq!(
+            typecheck(&parse("const f = (x: number) => x; f();").unwrap()),
+            Err(TypeError::ArityMismatch {
+                expected: 1,
+                actual: 0,
+                span: Span::new(28, 31),
+            })
+        );
+    }
+
+    #[test]
+    fn test_call_with_matching_args_is_ok() {
+        let term = Term::Call {
+            func: Box::new(parse("(x: number, y: boolean) => x").unwrap()),
+            args: vec![Term::Number(1.0, S), Term::True(S)],
+            span: S,
+        };
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+    }
+
+    #[test]
+    fn test_higher_order_func_is_ok() {
+        assert_eq!(
+            typecheck(&parse("(f: (x: number) => boolean) => f").unwrap()),
+            Ok(Type::Func {
+                params: vec![Param {
+                    name: "f".to_owned(),
+                    typ: Type::Func {
+                        params: vec![Param {
+                            name: "x".to_owned(),
+                            typ: Type::Number,
+                        }],
+                        ret_type: Box::new(Type::Boolean),
+                    },
+                }],
+                ret_type: Box::new(Type::Func {
+                    params: vec![Param {
+                        name: "x".to_owned(),
+                        typ: Type::Number,
+                    }],
+                    ret_type: Box::new(Type::Boolean),
+                }),
+            })
+        );
+
+        let term = parse("const twice = (f: () => number) => f() + f(); twice;").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(f: () => number) => number"
+        );
+
+        let term = parse("(f: () => boolean) => f() + 1").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number expected, but got boolean"
+        );
+    }
+
+    #[test]
+    fn test_curried_call_is_ok() {
+        let source = "const add = (x: number) => (y: number) => x + y; add(1)(2);";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        let source = "const add = (x: number) => (y: number) => x + y; add(1)(true);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got boolean");
+        assert_eq!(err.span().range(), 56..60);
+
+        let source = "const add = (x: number) => (y: number) => x + y; add(1)(2)(3);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "function type expected, but got number");
+    }
+
+    #[test]
+    fn test_call_on_paren_is_ok() {
+        let term = parse("((x: number) => x + 1)(2) + (1 + 2);").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("((x: number) => x)(true)").unwrap();
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got boolean");
+        assert_eq!(err.span().range(), 19..23);
+    }
+
+    #[test]
+    fn test_return_type_annotation() {
+        let term = parse("(x: number): number => x + 1").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number) => number"
+        );
+
+        let term =
+            parse("const f = (x: number): (y: number) => number => (y: number) => x + y; f(1)(2);")
+                .unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("(x: number): boolean => x").unwrap();
+        assert_eq!(
+            typecheck(&term),
+            Err(TypeError::ReturnTypeMismatch {
+                expected: Type::Boolean,
+                actual: Type::Number,
+                span: Span::new(24, 25),
+                annotation_span: Span::new(13, 20),
+            })
+        );
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "return type boolean expected, but the body has type number"
+        );
+        assert_eq!(err.span().range(), 24..25);
+
+        // 本体が誤っていても、呼び出し側では注釈の型を使う
+        let term = parse("const f = (): number => true; f() + 1;").unwrap();
+        let (typ, errors) = typecheck_all(&term);
+        assert_eq!(typ, Type::Number);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_recursive_func_is_ok() {
+        let source = "
+            function f(b: boolean): number {
+                return b ? f(false) + 1 : 0;
+            }
+            f(true);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 連続する関数宣言は互いに参照できる
+        let source = "
+            function even(b: boolean): boolean { return b ? odd(false) : true; }
+            function odd(b: boolean): boolean { return b ? even(false) : false; }
+            even;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(b: boolean) => boolean"
+        );
+
+        // 宣言で終わるプログラムの値は `undefined` である
+        let source = "function f(): number { return 1; }";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Undefined));
+    }
+
+    #[test]
+    fn test_recursive_func_is_ng() {
+        let source = "function f(x: number): number { return f(true); } f(1);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got boolean");
+        assert_eq!(err.span().range(), 41..45);
+
+        let source = "function f(): number { const x = true; return x; } f();";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::ReturnTypeMismatch {
+                expected: Type::Number,
+                actual: Type::Boolean,
+                span: Span::new(46, 47),
+                annotation_span: Span::new(14, 20),
+            }
+        );
+        assert_eq!(err.span().range(), 46..47);
+
+        // 間に他の文を挟んだ宣言は、前の宣言からは参照できない
+        let source = "
+            function f(): number { return g(); }
+            1;
+            function g(): number { return f(); }
+            g();
+        ";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown variable: g");
+    }
+
+    #[test]
+    fn test_call_with_mismatched_args_is_ng() {
+        let term = Term::Call {
+            func: Box::new(parse("(x: number, y: boolean) => x").unwrap()),
+            args: vec![Term::True(S), Term::Number(1.0, S)],
+            span: S,
+        };
+        assert_eq!(
+            typecheck(&term),
+            Err(TypeError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Boolean,
+                span: S,
+            })
+        );
+    }
+
+    // オブジェクト型は構造で比較すること
+
+    #[test]
+    fn test_object_is_ok() {
+        let term = parse("const o = { a: 1, b: { c: true } }; o;").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "{ a: number; b: { c: boolean } }"
+        );
+
+        let term = parse("const o = { a: 1, b: { c: true } }; o.b.c ? o.a : 0;").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        // プロパティの順序は問わない
+        let term = parse("const f = (o: { x: number; y: boolean }) => o.x; f({ y: true, x: 1 });")
+            .unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("true ? { a: 1, b: true } : { b: false, a: 2 };").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "{ a: number; b: boolean }"
+        );
+    }
+
+    #[test]
+    fn test_object_is_ng() {
+        // 食い違ったプロパティの値の位置で、期待される型を報告する
+        let term = parse("const f = (o: { x: number }) => o.x; f({ x: true });").unwrap();
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got boolean");
+        assert_eq!(err.span().range(), 44..48);
+
+        let term = parse("const f = (o: { x: number; y: number }) => o.x; f({ x: 1 });").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "{ x: number; y: number } expected, but got { x: number }"
+        );
+
+        let term = parse("const o = { a: 1 }; o.b;").unwrap();
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "property b does not exist on type { a: number }"
+        );
+        assert_eq!(err.span().range(), 20..23);
+
+        assert_eq!(
+            typecheck(&parse("const x = 1; x.a;").unwrap()),
+            Err(TypeError::UnknownProperty {
+                name: "a".to_owned(),
+                actual: Type::Number,
+                span: Span::new(13, 16),
+            })
+        );
+    }
+
+    // 部分型
+
+    #[test]
+    fn test_object_subtype() {
+        // 幅の部分型: 余分なプロパティがあってもよい
+        let term = parse("const f = (o: { x: number }) => o.x; f({ x: 1, y: true });").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        // 深さの部分型
+        let term = parse(
+            "const f = (o: { p: { x: number } }) => o.p.x; f({ p: { x: 1, y: 2 }, q: false });",
+        )
+        .unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("const f = (o: {}) => 1; f({ a: 1 }) + f({});").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("(): { x: number } => { x: 1, y: 2 }").unwrap();
+        assert_eq!(typecheck(&term).unwrap().to_string(), "() => { x: number }");
+
+        let term = parse("const f = (o: { p: { x: number } }) => 1; f({ p: { y: 2 } });").unwrap();
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "{ x: number } expected, but got { y: number }"
+        );
+        assert_eq!(err.span().range(), 49..57);
+    }
+
+    #[test]
+    fn test_func_subtype() {
+        // 引数の型は反変、戻り値の型は共変
+        let source = "
+            const apply = (f: (o: { x: number; y: number }) => { x: number }) => 1;
+            apply((o: { x: number }) => { x: o.x, z: true });
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        let source = "
+            const apply = (f: (o: { x: number }) => number) => 1;
+            apply((o: { x: number; y: number }) => o.y);
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "(o: { x: number }) => number expected, but got (o: { x: number; y: number }) => number"
+        );
+
+        let source = "
+            const apply = (f: () => { x: number }) => 1;
+            apply(() => { y: 1 });
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "{ x: number } expected, but got { y: number }"
+        );
+    }
+
+    #[test]
+    fn test_contextual_typing_is_ok() {
+        let source = "
+            const apply = (f: (x: number) => number, x: number) => f(x);
+            apply((x) => x + 1, 2);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+        // 戻り値の型注釈があっても引数の型は文脈から決める
+        let source = "
+            const apply = (f: (x: number) => number, x: number) => f(x);
+            apply((x): number => x + 1, 2);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // ジェネリック関数では、他の引数から推論した型引数を使う
+        let source = "
+            const apply = <T, U>(f: (x: T) => U, x: T) => f(x);
+            apply((x) => x + 1, 2);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+        let source = "
+            const map = <T, U>(x: T, f: (x: T) => U) => f(x);
+            map({ a: 1 }, (o) => o.a === 1);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Boolean));
+
+        // 期待される型はプロパティや条件演算子の分岐、戻り値の無名関数にも伝わる
+        let source = "
+            const call = (o: { f: (x: number) => boolean }) => o.f(1);
+            call({ f: (x) => x === 1 });
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Boolean));
+        let source = "
+            const apply = (f: (x: number) => number) => f(1);
+            apply(true ? (x) => x : (y) => y + 1);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+        let source = "
+            const curry = (f: (x: number) => (y: boolean) => number) => f(1)(true);
+            curry((x) => (y) => y ? x : 0);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 型注釈のある引数は、期待される引数の型の上位型であればよい
+        let source = "
+            const apply = (f: (x: number, y: boolean) => number) => f(1, true);
+            apply((x: number | boolean, y) => y ? 1 : 2);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+    }
+
+    #[test]
+    fn test_contextual_typing_is_ng() {
+        let source = "
+            const apply = (f: (x: number) => number) => f(1);
+            apply((x) => x ? 1 : 2);
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "boolean expected, but got number"
+        );
+
+        // 本体の食い違いは、本体の位置で期待される戻り値の型を報告する
+        let source = "const apply = (f: (x: number) => number) => f(1); apply((x) => true);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got boolean");
+        assert_eq!(err.span().range(), 63..67);
+
+        // 引数の数が異なる場合は文脈を使わない
+        let source = "const apply = (f: (x: number) => number) => f(1); apply((x, y) => x);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "parameter x needs a type annotation"
+        );
+
+        // 文脈の型が他の引数から推論できない型引数を含む場合
+        let source = "const run = <T>(f: (x: T) => number) => 1; run((x) => 1);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "parameter x needs a type annotation"
+        );
+
+        let source = "
+            const apply = (f: (x: number) => number) => f(1);
+            apply((x: boolean) => 1);
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "(x: number) => number expected, but got (x: boolean) => number"
+        );
+    }
+
+    #[test]
+    fn test_branch_join() {
+        let term = parse("true ? { a: 1, b: true } : { a: 2, c: 3 };").unwrap();
+        assert_eq!(typecheck(&term).unwrap().to_string(), "{ a: number }");
+
+        let term = parse("true ? { a: 1, b: true } : { a: 2, b: 3 };").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "{ a: number; b: boolean | number }"
+        );
+
+        let term = parse("true ? { p: { x: 1, y: 2 } } : { p: { x: 3 } };").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "{ p: { x: number } }"
+        );
+
+        // 関数型は引数の型の共通の部分型と、戻り値の型の共通の上位型をとる
+        let source = "
+            const f = (o: { x: number }) => { a: 1, b: 2 };
+            const g = (o: { y: number }) => { a: 3 };
+            true ? f : g;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(o: { x: number; y: number }) => { a: number }"
+        );
+
+        // 引数の型に共通の部分型がなければ合併型になる
+        let source = "
+            const f = (x: number) => 1;
+            const g = (x: boolean) => 1;
+            true ? f : g;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "((x: number) => number) | ((x: boolean) => number)"
+        );
+    }
+
+    // 合併型と typeof による絞り込み
+
+    #[test]
+    fn test_union_is_ok() {
+        let term = parse("const x = true ? 1 : 'a'; x;").unwrap();
+        assert_eq!(typecheck(&term).unwrap().to_string(), "number | string");
+
+        let term = parse("const f = (x: number | boolean) => x; f(1); f(true); f;").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number | boolean) => number | boolean"
+        );
+
+        // 合併型の部分型
+        let term =
+            parse("const f = (x: boolean | number | string) => 1; f(true ? 1 : false);").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let term = parse("(x: number | boolean): boolean | number => x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number | boolean) => boolean | number"
+        );
+
+        // 全ての型にあるプロパティは参照できる
+        let term = parse("(o: { a: number; b: boolean } | { a: string }) => o.a").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(o: { a: number; b: boolean } | { a: string }) => number | string"
+        );
+
+        let term = parse("typeof 1 === 'number';").unwrap();
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
+    }
+
+    #[test]
+    fn test_union_is_ng() {
+        let term = parse("(x: number | boolean) => x + 1").unwrap();
+        let err = typecheck(&term).unwrap_err();
+        assert_eq!(err.to_string(), "number expected, but got number | boolean");
+        assert_eq!(err.span().range(), 25..26);
+
+        let term = parse("const f = (x: number | boolean) => 1; f('a');").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number | boolean expected, but got string"
+        );
+
+        let term = parse("(o: { a: number; b: boolean } | { a: string }) => o.b").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "property b does not exist on type { a: number; b: boolean } | { a: string }"
+        );
+    }
+
+    #[test]
+    fn test_typeof_narrowing() {
+        let term = parse("(x: number | boolean) => typeof x === 'number' ? x + 1 : x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number | boolean) => number | boolean"
+        );
+
+        let term =
+            parse("(x: number | boolean) => typeof x === 'boolean' ? (x ? 1 : 0) : x + 1").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(x: number | boolean) => number"
+        );
+
+        // 文字列を左に書いてもよく、入れ子の条件演算子でさらに絞り込める
+        let source = "
+            const f = (x: number | string | (() => number)) =>
+                'string' === typeof x ? 0 : typeof x === 'function' ? x() : x + 1;
+            f;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(x: number | string | (() => number)) => number"
+        );
+
+        let term = parse("(o: { a: number } | number) => typeof o === 'object' ? o.a : o").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "(o: { a: number } | number) => number"
+        );
+
+        // 変数以外は絞り込まない
+        let term =
+            parse("(o: { a: number | boolean }) => typeof o.a === 'number' ? o.a + 1 : 0").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number expected, but got number | boolean"
+        );
+
+        // 絞り込まれた型は分岐の外には及ばない
+        let term = parse("(x: number | boolean) => (typeof x === 'number' ? 1 : 2) + x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number expected, but got number | boolean"
+        );
+    }
+
+    // 型エイリアス
+
+    #[test]
+    fn test_type_alias_is_ok() {
+        let source = "
+            type Point = { x: number; y: number };
+            const add = (p: Point, q: Point): Point => { x: p.x + q.x, y: p.y + q.y };
+            add({ x: 1, y: 2 }, { y: 3, x: 4 }).y;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // `type` は型エイリアスの宣言の外では変数名として使える
+        let source = "const type = 1; type + 1;";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 型はエイリアスの名前で表示する
+        let source = "
+            type Point = { x: number; y: number };
+            type Op = (p: Point) => Point;
+            const compose = (f: Op, g: Op) => (p: Point) => g(f(p));
+            compose;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(f: Op, g: Op) => (p: Point) => Point"
+        );
+
+        // 連続する宣言は後の宣言を参照できる
+        let source = "
+            type Line = { start: Point; end: Point };
+            type Point = { x: number; y: number };
+            (l: Line) => l.end.x;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(l: Line) => number"
+        );
+
+        // 関数型や合併型の別名
+        let source = "
+            type N = number | boolean;
+            type F = (x: N) => number;
+            const apply = (f: F, x: N) => f(x);
+            apply((x: N) => typeof x === 'number' ? x : 0, true);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+    }
+
+    #[test]
+    fn test_type_alias_is_ng() {
+        let source = "
+            type Point = { x: number; y: number };
+            const f = (p: Point) => p.x;
+            f({ x: 1 });
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "Point expected, but got { x: number }"
+        );
+
+        let source = "type Point = { x: number }; (p: Point) => p.z;";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "property z does not exist on type Point"
+        );
+
+        let source = "type F = (x: number) => number; (f: F) => f(true);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "number expected, but got boolean"
+        );
+
+        let source = "type N = number; (n: N) => n();";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "function type expected, but got N"
+        );
+    }
+
+    #[test]
+    fn test_type_alias_scope() {
+        let source = "(p: Point) => 1;";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown type: Point");
+        assert_eq!(err.span().range(), 1..9);
+
+        // 宣言より前の文からは参照できない
+        let source = "const f = (p: P): P => p; type P = number; f(1);";
+        let (_, errors) = typecheck_all(&parse(source).unwrap());
+        assert_eq!(
+            errors.iter().map(|e| e.span().range()).collect::<Vec<_>>(),
+            vec![11..15, 18..19]
+        );
+
+        // 関数本体で宣言した型エイリアスは外からは見えない
+        let source = "
+            function f(): number { type N = number; return 1; }
+            (n: N) => n;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "unknown type: N"
+        );
+
+        // 同じ名前の別の宣言とは区別し、後の宣言は番号を付けた名前で表示する
+        let source = "
+            type T = number;
+            const f = (x: T) => x;
+            type T = boolean;
+            const g = (x: T) => x;
+            f(1) + g(1);
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "T1 expected, but got number"
+        );
+        let source = "
+            type T = number;
+            const f = (x: T) => x;
+            type T = boolean;
+            type T1 = string;
+            (g: (x: T) => T1) => g(f(1));
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "T2 expected, but got T"
+        );
+        let source = "
+            type T = number;
+            function f(): T { type T = boolean; return 1; }
+            f;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "() => T"
+        );
+    }
+
+    #[test]
+    fn test_circular_type_alias() {
+        let source = "type A = B; type B = A | number; (a: A) => a;";
+        let (typ, errors) = typecheck_all(&parse(source).unwrap());
+        assert_eq!(
+            errors,
+            vec![
+                TypeError::CircularTypeAlias {
+                    name: "A".to_owned(),
+                    span: Span::new(0, 11),
+                },
+                TypeError::CircularTypeAlias {
+                    name: "B".to_owned(),
+                    span: Span::new(12, 32),
+                },
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "type alias A circularly references itself"
+        );
+        assert_eq!(errors[0].span().range(), 0..11);
+        assert_eq!(typ.to_string(), "(a: A) => A");
+
+        // オブジェクト型や関数型の中での参照は再帰的な型になる
+        let source = "type A = { next: A }; type C = A | number; type F = () => F; 1;";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+    }
+
+    #[test]
+    fn test_recursive_type_alias() {
+        let source = "
+            type List = { head: number; tail: List } | undefined;
+            function sum(l: List): number {
+                return typeof l === 'undefined' ? 0 : l.head + sum(l.tail);
+            }
+            sum({ head: 1, tail: { head: 2, tail: undefined } });
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        let source = "
+            type List = { head: number; tail: List } | undefined;
+            const f = (l: List) => 1;
+            f({ head: 1, tail: { head: true, tail: undefined } });
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "List expected, but got { head: number; tail: { head: boolean; tail: undefined } }"
+        );
+
+        let source = "
+            type List = { head: number; tail: List } | undefined;
+            (l: List) => l.head;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "property head does not exist on type List"
+        );
+    }
+
+    #[test]
+    fn test_recursive_type_equality() {
+        // 名前が異なっても、展開した構造が等しければ互いに部分型である
+        let source = "
+            type L1 = { head: number; tail: L1 } | undefined;
+            type L2 = { head: number; tail: L2 } | undefined;
+            const f = (l: L1): L2 => l;
+            const g = (l: L2): L1 => l;
+            g(f(undefined));
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "L1"
+        );
+
+        // 展開の段数が異なってもよい
+        let source = "
+            type A = { next: A };
+            type B = { next: { next: B } };
+            const f = (a: A): B => a;
+            (b: B): A => b;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(b: B) => A"
+        );
+
+        // 幅の部分型
+        let source = "
+            type Named = { name: string; next: Named } | undefined;
+            type Node = { next: Node } | undefined;
+            (n: Named): Node => n;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(n: Named) => Node"
+        );
+        let source = "
+            type Named = { name: string; next: Named } | undefined;
+            type Node = { next: Node } | undefined;
+            (n: Node): Named => n;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "return type Named expected, but the body has type Node"
+        );
+
+        let source = "
+            type L1 = { head: number; tail: L1 } | undefined;
+            type L2 = { head: boolean; tail: L2 } | undefined;
+            (l: L1): L2 => l;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "return type L2 expected, but the body has type L1"
+        );
+
+        // 共通の上位型を求めるときも展開し続けない
+        let source = "
+            type A = { next: A; a: number };
+            type B = { next: B; b: number };
+            (x: A, y: B) => true ? x : y;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(x: A, y: B) => { next: A | B }"
+        );
+    }
+
+    // ジェネリック関数
+
+    #[test]
+    fn test_generic_is_ok() {
+        let term = parse("<T>(x: T) => x").unwrap();
+        assert_eq!(typecheck(&term).unwrap().to_string(), "<T>(x: T) => T");
+
+        let source = "const id = <T>(x: T) => x; id<number>(1) + 1;";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        let source = "const id = <T>(x: T) => x; id<number | boolean>;";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(x: number | boolean) => number | boolean"
+        );
+
+        let source = "
+            const compose = <A, B, C>(f: (x: A) => B, g: (y: B) => C) => (x: A) => g(f(x));
+            const h = compose<number, boolean, string>((x: number) => true, (b: boolean) => b ? 'y' : 'n');
+            h;
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(x: number) => string"
+        );
+
+        let source = "
+            const apply = <T, U>(f: (x: T) => U, x: T): U => f(x);
+            apply<number, { a: number }>((x: number) => { a: x, b: true }, 2).a;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // ジェネリック関数を引数にとる (型引数の名前は問わない)
+        let source = "
+            const both = (f: <T>(x: T) => T) => f<number>(1) + (f<boolean>(true) ? 1 : 0);
+            both(<U>(y: U) => y);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 型引数は同じ名前の型エイリアスより優先される
+        let source = "type T = number; const f = <T>(x: T) => x; f<boolean>(true);";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Boolean));
+
+        // 入れ子のジェネリック関数
+        let source = "
+            const k = <T>(x: T) => <U>(y: U) => x;
+            const g = <U>(u: U) => k<U>(u);
+            g<number>(1)<boolean>(true) + 1;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+        // 外側と同じ名前の型引数は番号を付けて表示する
+        let term = parse("<T>(x: T) => <T>(y: T) => x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "<T>(x: T) => <T1>(y: T1) => T"
+        );
+        let term = parse("<T, T1>(x: T, y: T1) => <T>(z: T) => z").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "<T, T1>(x: T, y: T1) => <T2>(z: T2) => T2"
+        );
+        let term = parse("<T>(x: T) => <U>(y: U) => x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap().to_string(),
+            "<T>(x: T) => <U>(y: U) => T"
+        );
+    }
+
+    #[test]
+    fn test_generic_is_ng() {
+        let source = "const id = <T>(x: T) => x; id<number>(true);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "number expected, but got boolean"
+        );
+
+        let source = "const id = <T>(x: T) => x; id<number, boolean>(1);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::TypeArityMismatch {
+                expected: 1,
+                actual: 2,
+                span: Span::new(27, 46),
+            }
+        );
+        assert_eq!(err.span().range(), 27..46);
+
+        let term = parse("((x: number) => x)<number>").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "wrong number of type arguments: expected 0, but got 1"
+        );
+
+        // 型引数の型の値は、どの型の値であるかわからない
+        let term = parse("<T>(x: T) => x + 1").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "number expected, but got T"
+        );
+        let term = parse("<T>(x: T): number => x").unwrap();
+        assert_eq!(
+            typecheck(&term).unwrap_err().to_string(),
+            "return type number expected, but the body has type T"
+        );
+
+        let source = "const both = (f: <T>(x: T) => T) => 1; both((x: number) => x);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "<T>(x: T) => T expected, but got (x: number) => number"
+        );
+
+        // 型引数は関数の外からは見えない
+        let source = "const f = <T>(x: T) => x; (y: T) => y;";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "unknown type: T"
+        );
+    }
+
+    #[test]
+    fn test_type_arg_inference_is_ok() {
+        let source = "const id = <T>(x: T) => x; id(1) + 1;";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        let source = "
+            const compose = <A, B, C>(f: (x: A) => B, g: (y: B) => C) => (x: A) => g(f(x));
+            compose((x: number) => true, (b: boolean) => b ? 'y' : 'n');
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "(x: number) => string"
+        );
+
+        // オブジェクト型の中からも推論する
+        let source = "
+            const first = <T>(p: { fst: T; snd: T }) => p.fst;
+            first({ fst: { a: 1 }, snd: { a: 2, b: true } }).a;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 候補が部分型の関係にあれば大きい方を選ぶ
+        let source = "
+            const pick = <T>(b: boolean, x: T, y: T) => b ? x : y;
+            pick(true, { a: 1, b: 2 }, { a: 3 });
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "{ a: number }"
+        );
+
+        // 合併型の型引数を含まない部分は除いて推論する
+        let source = "
+            const orElse = <T>(x: T | undefined, y: T): T => typeof x === 'undefined' ? y : x;
+            orElse(true ? 1 : undefined, 2) + 1;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 関数型の引数に渡したジェネリック関数は、他の引数から推論した型引数で具体化する
+        let source = "
+            const app = <T, U>(f: (x: T) => U, x: T) => f(x);
+            app(<V>(y: V) => y, 1) + 1;
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+        let source = "
+            const app = <T, U>(x: T, f: (x: T, y: T) => U) => f(x, x);
+            app({ a: 1 }, <V>(y: V, z: V) => { v: z });
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "{ v: { a: number } }"
+        );
+
+        // 推論した型引数は呼び出しごとに異なる
+        let source = "
+            const id = <T>(x: T) => x;
+            id(true) ? id(1) : id(2);
+        ";
+        assert_eq!(typecheck(&parse(source).unwrap()), Ok(Type::Number));
+
+        // 明示した型引数は推論より優先される
+        let source = "const id = <T>(x: T) => x; id<number | boolean>(1);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap().to_string(),
+            "number | boolean"
+        );
+    }
+
+    #[test]
+    fn test_type_arg_inference_is_ng() {
+        let source = "const pair = <T>(x: T, y: T) => x; pair(1, true);";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::TypeArgumentConflict {
+                name: "T".to_owned(),
+                first: Box::new(Type::Number),
+                second: Box::new(Type::Boolean),
+                span: Span::new(43, 47),
+                first_span: Span::new(40, 41),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "type argument T has conflicting candidates number and boolean"
+        );
+        assert_eq!(err.span().range(), 43..47);
+
+        let source = "const f = <T>() => 1; f();";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type argument T cannot be inferred from the arguments"
+        );
+        assert_eq!(err.span().range(), 22..25);
+
+        // 推論した型に合わない引数
+        let source = "
+            const apply = <T>(f: (x: T) => T, x: T) => f(x);
+            apply((x: number) => x + 1, 2) + apply((x: boolean) => x, 1);
+        ";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "type argument T has conflicting candidates boolean and number"
+        );
+        let source = "const f = <T>(g: (x: T) => number) => 1; f(1);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "type argument T cannot be inferred from the arguments"
+        );
+        // 具体化できないジェネリック関数からは推論しない
+        let source = "const f = <T>(g: (x: T) => T) => g; f(<V>(y: V) => y);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "type argument T cannot be inferred from the arguments"
+        );
+        let source = "const f = <T>(x: T, g: (x: T) => T) => 1; f(1, 2);";
+        assert_eq!(
+            typecheck(&parse(source).unwrap()).unwrap_err().to_string(),
+            "(x: number) => number expected, but got number"
+        );
+
+        // 型引数を推論できなくても引数の数の誤りを報告する
+        let source = "const id = <T>(x: T) => x; id();";
+        let (_, errors) = typecheck_all(&parse(source).unwrap());
+        assert_eq!(
+            errors,
+            vec![TypeError::ArityMismatch {
+                expected: 1,
+                actual: 0,
+                span: Span::new(27, 31),
+            }]
+        );
+        let source = "const pair = <T>(x: T, y: T) => x; pair(1, true, 3);";
+        let (_, errors) = typecheck_all(&parse(source).unwrap());
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "wrong number of arguments: expected 2, but got 3",
+                "type argument T has conflicting candidates number and boolean",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subst_avoids_capture() {
+        let checker = Checker {
+            errors: vec![],
+            aliases: vec![],
+            type_param_count: Cell::new(2),
+        };
+        let var = |name: &str, id| Type::Var {
+            name: name.to_owned(),
+            id,
+        };
+        let generic = |param: usize, ret_type: Type| Type::Generic {
+            type_params: vec![TypeParam {
+                name: "U".to_owned(),
+                id: param,
+            }],
+            func: Box::new(Type::Func {
+                params: vec![Param {
+                    name: "y".to_owned(),
+                    typ: var("U", param),
+                }],
+                ret_type: Box::new(ret_type),
+            }),
+        };
+        // `<U>(y: U) => T` の `T` を外側の `U` に置き換えるときは、宣言された `U` を付け替える
+        let typ = generic(1, var("T", 0));
+        assert_eq!(
+            checker.subst(&typ, &[(0, var("U", 1))]),
+            generic(2, var("U", 1))
+        );
+        // 宣言された型引数自身は置き換えない
+        assert_eq!(checker.subst(&typ, &[(1, Type::Number)]), typ);
+    }
+
+    // 関数型以外は呼び出せないこと
+
+    #[test]
+    fn test_call_non_function_is_ng() {
+        assert_eq!(
+            typecheck(&parse("const x = 1; x();").unwrap()),
+            Err(TypeError::NotCallable {
+                actual: Type::Number,
+                span: Span::new(13, 14),
+            })
+        );
+    }
+
+    // 条件演算子の条件式と分岐の型
+
+    #[test]
+    fn test_if_is_ng() {
+        assert_eq!(
+            typecheck(&parse("1 ? 2 : 3").unwrap()),
+            Err(TypeError::NonBooleanCondition {
+                actual: Type::Number,
+                span: Span::new(0, 1),
+            })
+        );
+        assert_eq!(
+            typecheck(&parse("true ? 1 : false").unwrap()),
+            Ok(Type::Union(vec![Type::Number, Type::Boolean]))
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let source = "const f = () => 1;\nf() + y;";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(err.span().range(), 25..26);
+        assert_eq!(
+            format!(
+                "{}: {}",
+                LineIndex::new(source).line_col(err.span().start),
+                err
+            ),
+            "2:7: unknown variable: y"
+        );
+
+        let source = "const x = 1;\nx();";
+        let err = typecheck(&parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            format!(
+                "{}: {}",
+                LineIndex::new(source).line_col(err.span().start),
+                err
+            ),
+            "2:1: function type expected, but got number"
+        );
+    }
+
+    // 構文エラーの部分は型検査を省略すること
+
+    #[test]
+    fn test_error_term_is_skipped() {
+        let (term, errors) = parse_with_recovery("const x = 1 +; const y = x + 2; y ? 1 : 2;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            typecheck(&term),
+            Err(TypeError::NonBooleanCondition {
+                actual: Type::Number,
+                span: Span::new(32, 33),
+            })
+        );
+
+        let (term, errors) = parse_with_recovery("const f = (x: 'abcd') => x; f() + 1;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(typecheck(&term), Ok(Type::Number));
+
+        let (term, errors) = parse_with_recovery("const x = @; true ? x : false;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(typecheck(&term), Ok(Type::Boolean));
+    }
+
+    // 最初のエラーで止まらずに全てのエラーを報告すること
+
+    #[test]
+    fn test_typecheck_all() {
+        let (typ, errors) = typecheck_all(
+            &parse("const x = 1 + true; const y = z; const f = (a: number) => a; f() ? x : false;")
+                .unwrap(),
+        );
+        assert_eq!(typ.to_string(), "number | boolean");
+        assert_eq!(
+            errors,
+            vec![
+                TypeError::TypeMismatch {
+                    expected: Type::Number,
+                    actual: Type::Boolean,
+                    span: Span::new(14, 18),
+                },
+                TypeError::UnknownVariable {
+                    name: "z".to_owned(),
+                    span: Span::new(30, 31),
+                },
+                TypeError::ArityMismatch {
+                    expected: 1,
+                    actual: 0,
+                    span: Span::new(61, 64),
+                },
+                TypeError::NonBooleanCondition {
+                    actual: Type::Number,
+                    span: Span::new(61, 64),
+                },
+            ]
+        );
+    }
+
+    // エラーのあった部分から波及するエラーは報告しないこと
+
+    #[test]
+    fn test_typecheck_all_suppresses_cascading_errors() {
+        let (typ, errors) =
+            typecheck_all(&parse("const y = z; const w = y() + y; w ? y : 1;").unwrap());
+        assert_eq!(typ, Type::Number);
+        assert_eq!(
+            errors,
+            vec![
+                TypeError::UnknownVariable {
+                    name: "z".to_owned(),
+                    span: Span::new(10, 11),
+                },
+                TypeError::NonBooleanCondition {
+                    actual: Type::Number,
+                    span: Span::new(32, 33),
+                },
+            ]
+        );
+
+        let (typ, errors) = typecheck_all(&parse("const f = () => true ? 1 : z; f() + 1").unwrap());
+        assert_eq!(typ, Type::Number);
+        assert_eq!(errors.len(), 1);
+    }
+}
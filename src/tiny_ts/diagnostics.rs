@@ -0,0 +1,352 @@
+use crate::tiny_ts::span::{LineIndex, Span};
+use crate::tiny_ts::{arith, basic};
+
+/// 診断メッセージの出力形式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    /// 装飾なし (CI のログなど)
+    Plain,
+    /// ANSI エスケープシーケンスで色付けする (端末など)
+    Ansi,
+}
+
+/// ソースコード上の箇所とその説明
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// エラーの内容と、それを指し示すソースコード上の箇所
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// エラーの主な箇所 (`^` で下線を引く)
+    pub primary: Label,
+    /// 補足の箇所 (`-` で下線を引く)
+    pub secondary: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            primary: Label {
+                span,
+                message: String::new(),
+            },
+            secondary: vec![],
+        }
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// rustc や tsc のように、ファイル名と位置、該当行と下線を付けて整形する
+    ///
+    /// ```text
+    /// error: then and else have different types: number and boolean
+    ///  --> main.ts:1:1
+    ///   |
+    /// 1 | true ? 1 : false
+    ///   | ^^^^^^^^^^^^^^^^
+    ///   |        - this is `number`
+    ///   |            ----- this is `boolean`
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str, style: Style) -> String {
+        let index = LineIndex::new(source);
+        let position = index.line_col(self.primary.span.start);
+
+        let mut labels = std::iter::once((true, &self.primary))
+            .chain(self.secondary.iter().map(|label| (false, label)))
+            .map(|(is_primary, label)| (index.line_col(label.span.start), is_primary, label))
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|(line_col, _, _)| (line_col.line, line_col.column));
+
+        let max_line = labels
+            .iter()
+            .map(|(line_col, _, _)| line_col.line)
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(max_line.to_string().len());
+
+        let mut out = String::new();
+        out.push_str(&format!(
+            "{}: {}\n",
+            paint(style, RED, "error"),
+            paint(style, BOLD, &self.message)
+        ));
+        out.push_str(&format!(
+            "{}{} {}:{}\n",
+            pad,
+            paint(style, BLUE, "-->"),
+            file_name,
+            position
+        ));
+        out.push_str(&format!("{} {}\n", pad, paint(style, BLUE, "|")));
+
+        let mut current_line = None;
+        for (line_col, is_primary, label) in labels {
+            let text = index.line_text(line_col.line);
+            if current_line != Some(line_col.line) {
+                current_line = Some(line_col.line);
+                out.push_str(&format!(
+                    "{} {}\n",
+                    paint(style, BLUE, &format!("{:>1$} |", line_col.line, pad.len())),
+                    text
+                ));
+            }
+            let indent = line_col.column - 1;
+            let available = text.chars().count().saturating_sub(indent);
+            let length = source
+                .get(label.span.range())
+                .map_or(0, |s| s.chars().count())
+                .min(available)
+                .max(1);
+            let (mark, color) = if is_primary { ("^", RED) } else { ("-", BLUE) };
+            let underline = paint(style, color, &mark.repeat(length));
+            let message = if label.message.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", paint(style, color, &label.message))
+            };
+            out.push_str(&format!(
+                "{} {}{}{}\n",
+                paint(style, BLUE, &format!("{} |", pad)),
+                " ".repeat(indent),
+                underline,
+                message
+            ));
+        }
+        out
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+fn paint(style: Style, color: &str, text: &str) -> String {
+    match style {
+        Style::Plain => text.to_owned(),
+        Style::Ansi => format!("{}{}{}", color, text, RESET),
+    }
+}
+
+impl From<&arith::ParseError> for Diagnostic {
+    fn from(e: &arith::ParseError) -> Self {
+        Diagnostic::new(e.to_string(), e.span())
+    }
+}
+
+impl From<&arith::TypeError> for Diagnostic {
+    fn from(e: &arith::TypeError) -> Self {
+        match e {
+            arith::TypeError::BranchMismatch {
+                thn,
+                els,
+                span,
+                thn_span,
+                els_span,
+            } => Diagnostic::new(e.to_string(), *span)
+                .with_secondary(*thn_span, format!("this is `{}`", thn))
+                .with_secondary(*els_span, format!("this is `{}`", els)),
+            _ => Diagnostic::new(e.to_string(), e.span()),
+        }
+    }
+}
+
+impl From<&arith::EvalError> for Diagnostic {
+    fn from(e: &arith::EvalError) -> Self {
+        Diagnostic::new(e.to_string(), e.span())
+    }
+}
+
+impl From<&basic::ParseError> for Diagnostic {
+    fn from(e: &basic::ParseError) -> Self {
+        Diagnostic::new(e.to_string(), e.span())
+    }
+}
+
+impl From<&basic::TypeError> for Diagnostic {
+    fn from(e: &basic::TypeError) -> Self {
+        match e {
+            basic::TypeError::ReturnTypeMismatch {
+                expected,
+                span,
+                annotation_span,
+                ..
+            } => Diagnostic::new(e.to_string(), *span).with_secondary(
+                *annotation_span,
+                format!("return type `{}` declared here", expected),
+            ),
+            basic::TypeError::TypeArgumentConflict {
+                name,
+                first,
+                span,
+                first_span,
+                ..
+            } => Diagnostic::new(e.to_string(), *span).with_secondary(
+                *first_span,
+                format!("`{}` is inferred as `{}` here", name, first),
+            ),
+            _ => Diagnostic::new(e.to_string(), e.span()),
+        }
+    }
+}
+
+impl From<&basic::EvalError> for Diagnostic {
+    fn from(e: &basic::EvalError) -> Self {
+        Diagnostic::new(e.to_string(), e.span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_type_error() {
+        let source = "true ? 1 : false";
+        let err = arith::typecheck(&arith::parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: then and else have different types: number and boolean",
+                " --> main.ts:1:1",
+                "  |",
+                "1 | true ? 1 : false",
+                "  | ^^^^^^^^^^^^^^^^",
+                "  |        - this is `number`",
+                "  |            ----- this is `boolean`",
+                "",
+            ]
+            .join("\n")
+        );
+
+        let source = "const x = 1;\nconst y = x ? 2 : 3;\ny;";
+        let err = basic::typecheck(&basic::parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: boolean expected, but got number",
+                " --> main.ts:2:11",
+                "  |",
+                "2 | const y = x ? 2 : 3;",
+                "  |           ^",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_return_type_mismatch() {
+        let source = "const f = (x: number): boolean =>\n  x + 1;\nf;";
+        let err = basic::typecheck(&basic::parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: return type boolean expected, but the body has type number",
+                " --> main.ts:2:3",
+                "  |",
+                "1 | const f = (x: number): boolean =>",
+                "  |                        ------- return type `boolean` declared here",
+                "2 |   x + 1;",
+                "  |   ^^^^^",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_type_argument_conflict() {
+        let source = "const pair = <T>(x: T, y: T) => x;\npair(1, 'a');";
+        let err = basic::typecheck(&basic::parse(source).unwrap()).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: type argument T has conflicting candidates number and string",
+                " --> main.ts:2:9",
+                "  |",
+                "2 | pair(1, 'a');",
+                "  |      - `T` is inferred as `number` here",
+                "  |         ^^^",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let source = "true ?\n  1";
+        let err = arith::parse(source).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: `:` expected, but got end of input",
+                " --> main.ts:2:4",
+                "  |",
+                "2 |   1",
+                "  |    ^",
+                "",
+            ]
+            .join("\n")
+        );
+
+        let source = "const f = (x: number) => x;\nf(@);";
+        let err = basic::parse(source).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render("main.ts", source, Style::Plain),
+            [
+                "error: invalid token `@`",
+                " --> main.ts:2:3",
+                "  |",
+                "2 | f(@);",
+                "  |   ^",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_secondary_on_other_line() {
+        let diagnostic =
+            Diagnostic::new("message", Span::new(57, 58)).with_secondary(Span::new(0, 5), "note");
+        let source = (1..=10).map(|_| "a = 1").collect::<Vec<_>>().join("\n");
+        assert_eq!(
+            diagnostic.render("a.ts", &source, Style::Plain),
+            [
+                "error: message",
+                "  --> a.ts:10:4",
+                "   |",
+                " 1 | a = 1",
+                "   | ----- note",
+                "10 | a = 1",
+                "   |    ^",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let source = "1 + true";
+        let err = arith::typecheck(&arith::parse(source).unwrap()).unwrap_err();
+        let rendered = Diagnostic::from(&err).render("main.ts", source, Style::Ansi);
+        assert!(rendered.starts_with(
+            "\x1b[1;31merror\x1b[0m: \x1b[1mnumber expected, but got boolean\x1b[0m\n"
+        ));
+        assert!(rendered.contains("\x1b[1;31m^^^^\x1b[0m"));
+    }
+}
@@ -0,0 +1,29 @@
+/// 数値リテラルを JavaScript と同じく倍精度浮動小数点数として読む
+///
+/// 区切りの `_` は読み飛ばす。`radix` が 10 以外のときは接頭辞 (`0x`, `0b`) を除いた部分を渡す。
+pub(crate) fn parse_number(digits: &str, radix: u32) -> Option<f64> {
+    let digits = digits.replace('_', "");
+    if radix == 10 {
+        digits.parse().ok()
+    } else {
+        // 桁数の大きな値は精度を落として丸める
+        digits.chars().try_fold(0.0, |n, c| {
+            Some(n * f64::from(radix) + f64::from(c.to_digit(radix)?))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1_000.5", 10), Some(1000.5));
+        assert_eq!(parse_number("fF", 16), Some(255.0));
+        assert_eq!(parse_number("1_0", 2), Some(2.0));
+        assert_eq!(parse_number("2", 2), None);
+        // 2^53 を超える値は最も近い倍精度浮動小数点数に丸める
+        assert_eq!(parse_number("20000000000001", 16), Some(2f64.powi(53)));
+    }
+}
@@ -0,0 +1,176 @@
+/// ソースコード上の範囲 (バイト単位, `start..end`)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// `self` の先頭から `other` の末尾までを覆う範囲
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl From<std::ops::Range<usize>> for Span {
+    fn from(range: std::ops::Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+/// 位置情報を全て `Span::default()` に置き換える
+///
+/// テストで構文木やエラーを位置情報を無視して比較するために使う
+#[cfg(test)]
+pub(crate) trait ClearSpans {
+    fn clear_spans(&mut self);
+}
+
+#[cfg(test)]
+impl ClearSpans for Span {
+    fn clear_spans(&mut self) {
+        *self = Span::default();
+    }
+}
+
+#[cfg(test)]
+impl<T: ClearSpans + ?Sized> ClearSpans for Box<T> {
+    fn clear_spans(&mut self) {
+        (**self).clear_spans();
+    }
+}
+
+#[cfg(test)]
+impl<T: ClearSpans> ClearSpans for Option<T> {
+    fn clear_spans(&mut self) {
+        if let Some(value) = self {
+            value.clear_spans();
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T: ClearSpans> ClearSpans for Vec<T> {
+    fn clear_spans(&mut self) {
+        for value in self {
+            value.clear_spans();
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T: ClearSpans, E: ClearSpans> ClearSpans for Result<T, E> {
+    fn clear_spans(&mut self) {
+        match self {
+            Ok(value) => value.clear_spans(),
+            Err(err) => err.clear_spans(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<A: ClearSpans, B: ClearSpans> ClearSpans for (A, B) {
+    fn clear_spans(&mut self) {
+        self.0.clear_spans();
+        self.1.clear_spans();
+    }
+}
+
+/// 位置情報を全て `Span::default()` に置き換えた値を返す
+#[cfg(test)]
+pub(crate) fn without_spans<T: ClearSpans>(mut value: T) -> T {
+    value.clear_spans();
+    value
+}
+
+/// 行と列 (どちらも 1 始まり, 列は文字単位)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for LineCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// バイト位置から行と列への対応表
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..offset].chars().count() + 1;
+        LineCol {
+            line: line + 1,
+            column,
+        }
+    }
+
+    /// `line` 行目 (1 始まり) の内容 (改行を含まない)
+    pub fn line_text(&self, line: usize) -> &'a str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        self.source[start..end].trim_end_matches('\r')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span() {
+        assert_eq!(Span::new(1, 2).to(Span::new(5, 8)).range(), 1..8);
+        assert_eq!(Span::from(3..4).range(), 3..4);
+        assert_ne!(Span::new(0, 1), Span::new(2, 3));
+        assert_eq!(without_spans(Some(Span::new(2, 3))), Some(Span::default()));
+    }
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("true ?\n  1 :\n2");
+        assert_eq!(index.line_col(0), LineCol { line: 1, column: 1 });
+        assert_eq!(index.line_col(5), LineCol { line: 1, column: 6 });
+        assert_eq!(index.line_col(6), LineCol { line: 1, column: 7 });
+        assert_eq!(index.line_col(9), LineCol { line: 2, column: 3 });
+        assert_eq!(index.line_col(13), LineCol { line: 3, column: 1 });
+        assert_eq!(index.line_col(14), LineCol { line: 3, column: 2 });
+        assert_eq!(index.line_col(9).to_string(), "2:3");
+        assert_eq!(index.line_text(1), "true ?");
+        assert_eq!(index.line_text(2), "  1 :");
+        assert_eq!(index.line_text(3), "2");
+
+        let index = LineIndex::new("あい\nう");
+        assert_eq!(index.line_col(3), LineCol { line: 1, column: 2 });
+        assert_eq!(index.line_col(7), LineCol { line: 2, column: 1 });
+    }
+}